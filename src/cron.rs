@@ -0,0 +1,190 @@
+/*
+Cron expressions for recurring reminders, in the five-field form of crontab(5):
+
+    minute hour day-of-month month day-of-week
+
+Each field is *, a number, a range a-b, or a comma-separated list of
+these, and any of them may take a /step; a number with a step runs to
+the end of the field's range. Day-of-week is 0-7, 0 and 7 both being
+Sunday. As in cron, when day-of-month and day-of-week are both
+restricted (neither starts with *), a date matches if either field
+does. Minute and hour don't affect which dates match; when both are
+single numbers they give the reminder's display time.
+*/
+
+use chrono::{Datelike, NaiveDate};
+
+// long enough for every possible match to come around, e.g. Feb 29 on a Monday
+const SEARCH_DAYS: usize = 28 * 366;
+
+const FIELDS: [(&str, u32, u32); 5] = [
+    ("minute", 0, 59),
+    ("hour", 0, 23),
+    ("day-of-month", 1, 31),
+    ("month", 1, 12),
+    ("day-of-week", 0, 7),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expr: String,
+    time: Option<(u32, u32)>,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+impl Schedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 5 {
+            return Err(format!("cron expression \"{}\" must have 5 fields", expr));
+        }
+        let mut sets = [0; 5];
+        for (set, (&field, &(name, min, max))) in sets.iter_mut().zip(fields.iter().zip(FIELDS.iter())) {
+            *set = match parse_field(field, min, max) {
+                Some(values) => values,
+                None => return Err(format!("invalid {} field \"{}\" in cron expression \"{}\"", name, field, expr))
+            };
+        }
+        let single = |field: &str| field.parse::<u32>().ok();
+        Ok(Schedule {
+            expr: fields.join(" "),
+            time: single(fields[1]).zip(single(fields[0])),
+            days_of_month: sets[2],
+            months: sets[3],
+            // Sunday is both 0 and 7
+            days_of_week: if sets[4] & 1 << 7 != 0 { sets[4] | 1 } else { sets[4] },
+            dom_restricted: !fields[2].starts_with('*'),
+            dow_restricted: !fields[4].starts_with('*'),
+        })
+    }
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        if self.months & 1 << date.month() == 0 {
+            return false;
+        }
+        let dom = self.days_of_month & 1 << date.day() != 0;
+        let dow = self.days_of_week & 1 << date.weekday().num_days_from_sunday() != 0;
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+    // first matching date on or after from
+    pub fn next(&self, from: NaiveDate) -> Option<NaiveDate> {
+        from.iter_days().take(SEARCH_DAYS).find(|&date| self.matches(date))
+    }
+    pub fn time(&self) -> Option<String> {
+        self.time.map(|(hour, minute)| format!("{:02}:{:02}", hour, minute))
+    }
+}
+
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+// bit set of the values a field allows, or None if it's invalid
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut values = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<usize>().ok().filter(|&step| step > 0)?)),
+            None => (item, None)
+        };
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            (lo.parse().ok()?, hi.parse().ok()?)
+        } else {
+            let n = range.parse().ok()?;
+            (n, if step.is_some() { max } else { n })
+        };
+        if lo < min || hi > max || lo > hi {
+            return None;
+        }
+        values = (lo..=hi).step_by(step.unwrap_or(1)).fold(values, |values, n| values | 1u64 << n);
+    }
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn values(set: u64) -> Vec<u32> {
+        (0..64).filter(|n| set & 1 << n != 0).collect()
+    }
+
+    #[test]
+    fn field_ranges_lists_and_steps() {
+        assert_eq!(parse_field("*", 1, 12).map(values), Some((1..=12).collect()));
+        assert_eq!(parse_field("*/15", 0, 59).map(values), Some(vec!(0, 15, 30, 45)));
+        assert_eq!(parse_field("1-10/3", 1, 31).map(values), Some(vec!(1, 4, 7, 10)));
+        assert_eq!(parse_field("20/5", 1, 31).map(values), Some(vec!(20, 25, 30)));
+        assert_eq!(parse_field("1,15,3-4", 1, 31).map(values), Some(vec!(1, 3, 4, 15)));
+    }
+
+    #[test]
+    fn invalid_fields() {
+        assert_eq!(parse_field("0", 1, 31), None);
+        assert_eq!(parse_field("5-3", 1, 31), None);
+        assert_eq!(parse_field("*/0", 0, 59), None);
+        assert_eq!(parse_field("x", 0, 59), None);
+        assert_eq!(parse_field("1,", 0, 59), None);
+    }
+
+    #[test]
+    fn errors_name_the_field() {
+        assert_eq!(Schedule::parse("0 9 * 13 *"),
+            Err("invalid month field \"13\" in cron expression \"0 9 * 13 *\"".to_string()));
+        assert_eq!(Schedule::parse("0 9 * *"),
+            Err("cron expression \"0 9 * *\" must have 5 fields".to_string()));
+    }
+
+    #[test]
+    fn seven_is_sunday() {
+        let schedule = Schedule::parse("0 9 * * 7").unwrap();
+        // 2025-06-01 is a Sunday
+        assert!(schedule.matches(date(2025, 6, 1)));
+        assert!(!schedule.matches(date(2025, 6, 2)));
+        assert_eq!(schedule.next(date(2025, 6, 2)), Some(date(2025, 6, 8)));
+    }
+
+    #[test]
+    fn day_of_month_or_day_of_week_when_both_restricted() {
+        // the 13th, or any Friday
+        let schedule = Schedule::parse("0 0 13 * 5").unwrap();
+        assert!(schedule.matches(date(2025, 6, 13)));
+        assert!(schedule.matches(date(2025, 6, 6)));
+        assert!(!schedule.matches(date(2025, 6, 7)));
+        // with day-of-week unrestricted, only the 13th
+        let schedule = Schedule::parse("0 0 13 * *").unwrap();
+        assert!(schedule.matches(date(2025, 6, 13)));
+        assert!(!schedule.matches(date(2025, 6, 6)));
+        // a stepped * still counts as unrestricted, so both must match
+        let schedule = Schedule::parse("0 0 */2 * 5").unwrap();
+        assert!(!schedule.matches(date(2025, 6, 6)));
+        assert!(schedule.matches(date(2025, 6, 13)));
+    }
+
+    #[test]
+    fn time_only_for_single_minute_and_hour() {
+        assert_eq!(Schedule::parse("30 9 * * *").unwrap().time(), Some("09:30".to_string()));
+        assert_eq!(Schedule::parse("*/30 9 * * *").unwrap().time(), None);
+    }
+
+    #[test]
+    fn feb_29_is_found_years_ahead() {
+        assert_eq!(Schedule::parse("0 0 29 2 *").unwrap().next(date(2025, 1, 1)), Some(date(2028, 2, 29)));
+        assert!(Schedule::parse("0 0 30 2 *").unwrap().next(date(2025, 1, 1)).is_none());
+    }
+}
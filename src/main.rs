@@ -7,8 +7,33 @@ NAME
 
 USAGE
 
-    remind -- show reminders for next seven days
+    remind [options] -- show reminders for next seven days
     remind [year] month day message -- add reminder to database
+    remind in n days message -- add reminder n days (or weeks) from today
+    remind cron "expression" message -- add reminder on a cron schedule
+    remind --before reminder days message -- add reminder days before another
+    remind --daily-from start --daily-to end message -- add reminder for each day
+    remind rename-tag old new -- rename tag #old to #new in all reminders
+    remind tag add|remove tag reminder -- add or remove a tag on reminders
+    remind review -- step through today's reminders
+    remind note reminder [note] -- set or, with no note, remove a reminder's note
+    remind show reminder -- show a reminder with its note
+    remind next-date reminder -- print the date a reminder next occurs
+    remind explain reminder -- tell why a reminder is or isn't listed
+    remind open reminder -- open a reminder's link in the default browser
+    remind repair -- tidy up the database
+    remind import --vcf file -- add birthdays from a vCard file
+    remind export --report -- print an overview of all the reminders
+    remind doctor -- report recurring reminders that fall on the same date with the same message
+    remind info -- print details of remind and its database for a bug report
+    remind history -- show the log of changes to the database
+    remind rotate -- archive past reminders by year instead of deleting them
+    remind archive list year -- show the reminders archived for a year
+    remind sync-merge base theirs -- merge another copy of the database into this one
+    remind shift offset -- move every reminder by offset, like +7d or -2w
+
+    Options may be given after any of these commands other than those
+    adding a reminder, as well as before it; an argument -- ends them.
 
 DESCRIPTION
 
@@ -19,14 +44,324 @@ DESCRIPTION
 
     Year is optional, and must be an integer greater than 99; if no
     year is given, the reminder applies to all years (for instance,
-    birthdays).
+    birthdays). Everything after an argument -- following the day is
+    the message exactly as given, as in
+
+        remind 7 4 -- 2025 budget review
 
     If remind is called with no arguments, it writes to standard
-    output all reminders that occur within the next seven days. If
+    output all reminders that occur within the next seven days,
+    followed by a summary line such as "3 reminders in the next 7 days
+    (1 today)"; the very first time, when there is no database yet, it
+    also explains on standard error how to add a reminder. If
     remind is called with arguments giving a date and message, a
     reminder is added to the database. Any time remind is called,
     all past reminders are deleted from the database.
 
+    A message may begin with a priority, !1 being the highest, as in
+
+        remind 10 20 !1 renew passport
+
+    or the priority may be given with --priority. The priority is
+    shown before the message in the listing, and --sort priority lists
+    the reminders with a priority first, highest first. A message that
+    itself begins with, say, !1 is written after --.
+
+    In the listing, {year} in a message is replaced by the year of the
+    reminder's date, and {year-1} or {year+1} by the year before or
+    after, so that
+
+        remind 4 15 file {year-1} taxes
+
+    lists as "file 2024 taxes" in 2025 and "file 2025 taxes" in 2026;
+    the database keeps the placeholders.
+
+    A reminder may be added for a number of days or weeks from today,
+    as in
+
+        remind in 10 days call the garage
+
+    which adds a reminder with the date, year included, 10 days from
+    today, to be deleted once past like any other.
+
+    A reminder may instead follow a cron schedule, given by the
+    day-of-month, month and day-of-week fields of a crontab(5)
+    expression, for instance the 1st and 15th of every month:
+
+        cron "0 9 1,15 * *" submit timesheet
+
+    Each day in the window that the schedule matches is listed, with
+    the minute and hour fields shown as the time when both are single
+    numbers. Cron reminders are never deleted as past.
+
+    A reminder may be set a number of days before another, for
+    instance three days before a birthday:
+
+        remind --before "Mom birthday" 3 order flowers
+
+    which the database holds as
+
+        before 3 "Mom birthday" order flowers
+
+    Its date is worked out afresh on each run from the next occurrence
+    of the other reminder, picked by text from its message as for
+    remind show below, that is at least that many days away. If the
+    other reminder is missing, or the text picks more than one, remind
+    warns and the reminder is kept but not listed.
+
+    When the database holds cron or before reminders, its first line is the
+    header "#remind-format 2"; a database without a header is format
+    1. A database in a newer format than this version of remind
+    understands is listed as far as possible but never changed.
+
+    Reminders are listed, and kept in the database, in date order;
+    reminders on the same date are ordered by time, reminders without
+    a time first, then by priority, then by message ignoring case, and
+    finally by how they are written in the database. With sort_ties = order in the
+    config file, reminders on the same date and at the same time are
+    instead left in the order they are written in the database, with
+    a new reminder after the others; this is the order they were
+    added only for those added while the setting is on, since any run
+    without it writes them back ordered by message. --sort-ties order
+    does the same for a single run. With --sort-ties priority they are
+    ordered by priority even before time.
+
+    remind review shows each reminder due today in turn and reads an
+    action for it from standard input: k to keep it, d to mark it done,
+    deleting it, s or S to snooze it a day or a week, e to edit its
+    message, written as when adding a reminder, with the priority, link
+    and note it had unless the edit gives others, or q to quit.
+    Snoozing a recurring reminder adds a one-off copy on the later date.
+    Nothing is written until the review ends, so interrupting it with
+    Ctrl-C abandons all changes.
+
+    A reminder may carry a note, kept at the end of its line in the
+    database as note="...", with any quotes, backslashes and line
+    breaks escaped, for details such as an address that would clutter
+    the listing. Notes are shown only by remind show and by the
+    --details option. The reminder for note, show, explain and open is picked by its
+    line number in the database or by text from its message, ignoring
+    case; text matching one message exactly picks it over others that
+    only contain the text. --first and --last pick the first and last
+    reminders in the database, which is in date order.
+
+    remind repair reads a database that may have stray spaces, blank
+    lines and duplicate reminders, and rewrites it one reminder per
+    line, single-spaced, in order, without the duplicates or past
+    reminders; it then reports what it changed. Repairing a database
+    a second time changes nothing. With --case-insensitive, reminders
+    whose messages differ only in case or spacing are duplicates too,
+    and the first of them is kept.
+
+    remind shift moves every dated reminder by a number of days (d) or
+    weeks (w), and every yearless reminder to the month and day as
+    many days from its next occurrence; cron reminders stay on their
+    schedules, and before reminders stay before their other reminders.
+    A dated reminder moved into the past is deleted, and is counted
+    apart. It asks before moving anything, unless --yes is given, and
+    with --dry-run only shows what would move.
+
+    remind import --vcf reads the contacts in a vCard file, such as one
+    exported from an address book, and adds a yearly reminder "Name
+    birthday" for each contact with a birthday, given as 1990-04-02,
+    19900402, --04-02 or --0402; birthdays it can't read are skipped
+    and counted, as are ones already in the database.
+
+    remind export --report prints three sections: Upcoming, the
+    reminders listed as remind would with no arguments; Recurring
+    events, the yearly and cron reminders in calendar order; and All
+    dated reminders, in date order.
+
+    remind rotate keeps the past reminders that would be deleted,
+    appending each to the archive for its year, such as .reminders.2024
+    in the user's home directory, in the same form as the database;
+    remind archive list 2024 shows that archive.
+
+    remind next-date prints just the date on which a reminder next
+    occurs, as 2025-06-01, or with --format epoch-day as the number of
+    days since 1970-01-01, for scripts; it fails if no one reminder
+    matches.
+
+    remind doctor looks over the next year for dates on which two or
+    more different recurring reminders with the same message, ignoring
+    case and spacing, both occur, such as a cron reminder every Monday and
+    another on the 1st of the month, and reports each such date with
+    the reminders involved.
+
+    remind info prints the version of remind, where it looks for the
+    database and whether it's there, the home directory, the date it
+    takes as today with the time zone, and how many reminders of each
+    kind there are; it changes nothing, not even to delete past
+    reminders.
+
+    remind sync-merge merges into the database the changes made to
+    another copy of it, theirs, since the two were last the same, as
+    in base. Reminders added to theirs since base are added, and those
+    deleted from it are deleted. A reminder changed in both, or changed
+    in one and deleted in the other, is a conflict: it is reported, and
+    left as it is in this database. A reminder counts as changed when
+    its line is replaced by a single other line for the same date.
+    With --case-insensitive, reminders whose messages differ only in
+    case or spacing are the same.
+
+    Every change to the database, whether adding, deleting, moving,
+    editing or purging a past reminder, is logged with the time in
+    .reminders.log in the user's home directory, which remind history
+    shows. A failure to write the log is reported but doesn't stop
+    remind. Set history = off in the config file to turn off logging.
+
+    A word in a message beginning with @http:// or @https:// is a
+    link, for instance to a meeting. Links are left out of the listing
+    unless the --verbose option is given, and remind open opens one.
+    A message has one link, its last; any other such words are just
+    part of the message.
+
+    A word in a message beginning with # is a tag, for instance
+    #work. Tags are matched whole, so renaming #work leaves
+    #workshop alone. remind tag add work adds #work to the end of
+    every reminder the text picks, or one picked by line number, that
+    doesn't already have it, and remind tag remove work removes it.
+
+OPTIONS
+
+    --align    pad dates to the widest date shown so messages line up
+    --anchor next|nearest
+               list each yearly reminder at its next occurrence, the
+               default, or at its occurrence nearest today or the
+               start of the window, even if that's past, so long as
+               it's in the window; with --include-past, a birthday two
+               days ago is then listed rather than next year's
+    --badge    instead of listing, print [n] for a shell prompt if n
+               reminders are due today, or nothing if none are
+    --badge-format format
+               print the badge as format, with {n} replaced by the
+               number due today; implies --badge
+    --before reminder days
+               add the reminder that many days before the given one
+    --case-insensitive
+               with repair or sync-merge, take reminders differing
+               only in the case or spacing of their messages as the same
+    --daily-from start, --daily-to end
+               add a separate reminder for each day from start to end,
+               written as 2025-06-01, at most 366 days
+    --details  show notes under their reminders in the listing
+    --dry-run  with repair or shift, show what would change, without
+               changing it
+    --ephemeral
+               use a throwaway database in the temp directory, deleted
+               on exit, instead of ~/.reminders
+    --interval seconds
+               with --watch, how often to check, by default every 60
+    --format iso|epoch-day
+               with next-date, print the date as 2025-06-01, the
+               default, or as the number of days since 1970-01-01
+    --group-by-tag
+               list the reminders under a heading for each of their
+               tags, in alphabetical order, and then those with no tag
+               under (untagged)
+    --include-past
+               also list the reminders in as many days before the
+               window as it has, 7 by default; see --past-days and,
+               for yearly reminders, --anchor
+    --legacy   list the reminders in the next seven days exactly as the
+               original remind did, each as [year] month day message
+               in the order they are in the database with no summary,
+               ignoring the listing options and settings in the config
+               file; a reminder added with --legacy goes at the end
+    --nearest  when adding a reminder whose day is past the end of its
+               month, as in 7 32, add it on the month's last day
+               instead, with a warning; February has 29 days unless a
+               year is given, so 2 30 gives a reminder every leap year
+    --new-only list only reminders not already listed today, keeping
+               track of what has been listed in remind/seen in the
+               user's state directory (~/.local/state on Linux)
+    --no-summary
+               don't print the summary line after the listing
+    --output file
+               write the output to file, truncating it, instead of to
+               standard output; errors still go to standard error
+    --past-days days
+               also list the reminders in the given number of days
+               before the window, not deleting them as past until a
+               run without this option
+    --preview-purge
+               print the past reminders that would be deleted from the
+               database, as they are written there, and stop without
+               changing anything
+    --quiet    print nothing, not even the summary, when there are no
+               reminders to list
+    --reset-seen
+               forget which reminders --new-only has listed
+    --reverse  list the reminders farthest out first
+    --verbose  show links in the listing
+    --watch    keep running, printing each reminder as it comes due
+               today, until interrupted
+    --window start:days
+               list the reminders in the given number of days from
+               the date start, written as 2025-12-01, rather than in
+               the next seven days; past reminders are still deleted
+               as of today
+    --wrap columns
+               wrap the listing at the given number of columns,
+               continuing long messages on lines lined up under the
+               message; a word too long for a line is broken
+    --yes      with shift, move the reminders without asking first
+    --priority n
+               give the reminder being added priority n, 1 being the
+               highest
+    --sort priority|date
+               list the reminders by priority, highest first, and then
+               by date, rather than by date, the default
+    --sort-ties order|message|priority
+               order reminders on the same date and at the same time
+               as they are written in the database, by priority and
+               then message, the default, or on the same date by
+               priority before time; see
+               sort_ties under CONFIGURATION
+    --status-file file
+               also write to file, as JSON, when it was written, how
+               many reminders are due today and the date of the next
+               one, for instance for a status bar
+    --two-digit-years [pivot=N]
+               convert the database once, taking a line starting with
+               three numbers, the first of which is 0 to 99 and the
+               others a valid month and day, as having a two-digit
+               year, unless the first two are a valid month and day,
+               as in 4 2 10 years married: years from N (by default 70, or two_digit_years
+               in the config file) up are in the 1900s, the rest in
+               the 2000s; the lines changed are shown, the years are
+               written back in full, and nothing else is done
+
+CONFIGURATION
+
+    Defaults for some options may be set in the config file remind/config
+    in the user's config directory (~/.config on Linux), one per line:
+
+        two_digit_years = 70
+        history = off
+        sort_ties = order
+        day_rollover_hour = 3
+        default_command = today
+        expand_recurrence_label = on
+
+    sort_ties sets --sort-ties for every run, which for order is needed
+    to keep reminders in the order they were added; see above.
+
+    default_command sets what remind does when run with no arguments:
+    week, the default, lists the reminders in the next seven days;
+    today lists just today's; list lists every reminder in the
+    database, without a summary; and next lists the next reminder to
+    come.
+
+    With expand_recurrence_label on, the listing shows after each
+    message how the reminder recurs, as (yearly), (cron 0 9 1,15 * *)
+    or (3 days before Mom birthday); reminders for one date show
+    nothing more.
+
+    With day_rollover_hour set, today lasts until that hour of the next
+    morning, so that at 1am with the setting above the reminders for
+    the day before are still today's, and aren't yet deleted as past.
+
 EXAMPLE
 
     $ date
@@ -46,124 +381,1879 @@ EXAMPLE
     2019 7 2 lunch with Pat
 */
 
+mod cron;
+
 use itertools::Itertools;
 use chrono::prelude::*;
 
+// the newest database format this version reads and writes
+const FORMAT_VERSION: u32 = 2;
+const FORMAT_HEADER: &str = "#remind-format ";
+// most reminders --daily-from and --daily-to add at once
+const MAX_DAILY: i64 = 366;
+const NOTE_START: &str = "note=\"";
+// the commands after which options may be given too
+const COMMANDS: [&str; 18] = ["archive", "doctor", "explain", "export", "history", "import", "info", "next-date", "note",
+    "open", "rename-tag", "repair", "review", "rotate", "shift", "show", "sync-merge", "tag"];
+// arguments to commands that look like options
+const COMMAND_ARGS: [&str; 4] = ["--first", "--last", "--report", "--vcf"];
+
 fn main() -> Result<(), String> {
-    let mut r = Reminders::new(".reminders")?;
-    let args = std::env::args().skip(1);
-    if args.len() == 0 {
-        print!("{}", r.stringify(7));
-    } else {
-        r.add(r.parse_item(args)?);
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut options = Options::parse(&mut args)?;
+    if options.reset_seen {
+        if let Some(path) = seen_path().filter(|path| path.exists()) {
+            if let Err(m) = std::fs::remove_file(&path) {
+                return Err(format!("could not remove {}: {}", path.display(), m));
+            }
+        }
+    }
+    if options.watch && args.is_empty() {
+        return watch(&options);
+    }
+    if options.two_digit_years && !args.is_empty() {
+        return Err("--two-digit-years converts the database, and takes no command".to_string());
+    }
+    if options.preview_purge && !args.is_empty() {
+        return Err("--preview-purge only shows what would be deleted, and takes no command".to_string());
+    }
+    let repair = args.first().is_some_and(|arg| arg == "repair");
+    let mut r = Reminders::new(".reminders", &options, repair)?;
+    let mut output = String::new();
+    // false for the commands that only show what they would do
+    let mut save = true;
+    match args.first().map(|arg| arg.as_str()) {
+        None if options.preview_purge => {
+            output = r.preview_purge();
+            save = false;
+        }
+        None if options.two_digit_years => {
+            r.writable()?;
+            output = r.repairs.years.iter().map(|line| format!("{}\n", line)).join("");
+            output += &format!("expanded {}, {} purged\n", plural(r.repairs.years.len(), "two-digit year"), plural(r.purged.len(), "past reminder"));
+        }
+        None if options.legacy => output = r.legacy_listing(),
+        None => match &options.badge {
+            Some(format) => output = r.badge(format),
+            None => {
+                if !options.ephemeral && r.reminder_items.is_empty() && !r.path.exists() {
+                    onboard(config_path("onboarded"));
+                }
+                output = r.default_listing(&mut options)?;
+            }
+        },
+        // the message of a reminder added with these may begin with a command's name
+        Some(_) if options.before.is_some() => {
+            r.writable()?;
+            let (referent, days) = options.before.clone().unwrap();
+            r.add_before(&referent, days, args.join(" "))?;
+        }
+        Some(_) if options.daily_from.is_some() || options.daily_to.is_some() => {
+            r.writable()?;
+            r.add_daily(options.daily_from, options.daily_to, args.join(" "))?;
+        }
+        Some("show") if args.len() == 2 => output = r.show(&args[1])?,
+        Some("next-date") if args.len() == 2 => output = r.next_date(&args[1], options.epoch_day)?,
+        Some("explain") if args.len() == 2 => output = r.explain(&args[1], &options)?,
+        Some("open") if args.len() == 2 => {
+            let item = &r.reminder_items[r.select_one(&args[1])?];
+            match &item.url {
+                Some(url) => if let Err(m) = opener(url).spawn() {
+                    return Err(format!("could not open {}: {}", url, m));
+                },
+                None => return Err(format!("reminder \"{}\" has no link", item.message))
+            }
+        }
+        Some("doctor") if args.len() == 1 => output = r.doctor(),
+        Some("info") if args.len() == 1 => {
+            output = r.info();
+            save = false;
+        }
+        Some("export") if args.len() == 2 && args[1] == "--report" => output = r.report(&options),
+        Some("archive") if args.len() == 3 && args[1] == "list" => output = r.read_archive(&args[2])?,
+        Some(_) if r.format > FORMAT_VERSION => r.writable()?,
+        Some("review") if args.len() == 1 => output = r.review(std::io::stdin().lock(), std::io::stdout())?,
+        Some("note") if args.len() >= 2 => {
+            let i = r.select_one(&args[1])?;
+            let note = args[2..].join(" ");
+            let old = r.reminder_items[i].to_string();
+            r.reminder_items[i].note = if note.is_empty() { None } else { Some(note) };
+            let entry = format!("edit {} -> {}", old, r.reminder_items[i]);
+            r.history.push(entry);
+        }
+        Some("rename-tag") => {
+            if args.len() != 3 {
+                return Err("usage: remind rename-tag old new".to_string());
+            }
+            let n = r.rename_tag(&args[1], &args[2])?;
+            output = format!("renamed tag in {}\n", plural(n, "reminder"));
+        }
+        Some("tag") => {
+            let add = match args.get(1).map(|arg| arg.as_str()) {
+                Some("add") if args.len() >= 4 => true,
+                Some("remove") if args.len() >= 4 => false,
+                _ => return Err("usage: remind tag add|remove tag reminder".to_string())
+            };
+            let n = r.tag(&args[2], &args[3..].join(" "), add)?;
+            output = format!("{} {}\n", if add { "tagged" } else { "untagged" }, plural(n, "reminder"));
+        }
+        Some("shift") if args.len() == 2 => {
+            let days = parse_offset(&args[1])?;
+            let shifted = r.shifted(days);
+            let changed = r.reminder_items.iter().zip(&shifted).filter(|(old, new)| old != new).collect::<Vec<_>>();
+            // dated reminders moved into the past are purged like any other past reminder
+            let (past, moved): (Vec<_>, Vec<_>) = changed.iter().partition(|(_, new)| r.is_past(new));
+            let deleting = |verb: &str| match past.len() {
+                0 => String::new(),
+                n => format!(", {} {} moved into the past", verb, n)
+            };
+            if options.dry_run {
+                output = changed
+                    .iter()
+                    .map(|(old, new)| format!("{} -> {}{}\n", old, new, if r.is_past(new) { " (past, deleted)" } else { "" }))
+                    .join("");
+                output += &format!("would move {}{}\n", plural(moved.len(), "reminder"), deleting("deleting"));
+                save = false;
+            } else {
+                let question = format!("move {} by {}{}? [y/N] ", plural(moved.len(), "reminder"), args[1], deleting("deleting"));
+                if !options.yes && !changed.is_empty() && !confirm(&question)? {
+                    return Err("nothing moved".to_string());
+                }
+                output = format!("moved {}{}\n", plural(moved.len(), "reminder"), deleting("deleted"));
+                let entries = moved.iter().map(|(old, new)| format!("move {} -> {}", old, new)).collect::<Vec<_>>();
+                r.history.extend(entries);
+                r.reminder_items.clear();
+                for item in shifted {
+                    r.add(item);
+                }
+            }
+        }
+        Some("repair") if args.len() == 1 => {
+            output = format!("{} removed, {} purged, {} reformatted, {} removed\n",
+                plural(r.repairs.duplicates, "duplicate"), plural(r.purged.len(), "past reminder"),
+                plural(r.repairs.reformatted, "line"), plural(r.repairs.blank, "blank line"));
+            if options.dry_run {
+                output = r.persistable_lines() + &output;
+                save = false;
+            } else {
+                r.history.push(format!("repair {}", output.trim_end()));
+            }
+        }
+        Some("history") if args.len() == 1 => output = r.read_history()?,
+        Some("import") if args.len() == 3 && args[1] == "--vcf" => {
+            let vcf = match std::fs::read_to_string(&args[2]) {
+                Ok(vcf) => vcf,
+                Err(e) => return Err(format!("could not read {}: {}", args[2], e))
+            };
+            output = r.import_vcf(&vcf);
+        }
+        Some("sync-merge") => {
+            if args.len() != 3 {
+                return Err("usage: remind sync-merge base theirs".to_string());
+            }
+            let base = load_copy(&args[1])?;
+            let theirs = load_copy(&args[2])?;
+            output = r.sync_merge(&base, &theirs, options.case_insensitive);
+        }
+        Some("rotate") if args.len() == 1 => output = r.rotate()?,
+        Some(command) if COMMANDS.contains(&command) => return Err(format!("usage: remind {}", command_usage(command))),
+        Some(_) => r.add_new(r.parse_item(args.into_iter(), options.nearest)?),
+    }
+    write_output(&output, options.output.as_deref())?;
+    if !save {
+        return Ok(());
+    }
+    if let Some(path) = &options.status_file {
+        if let Err(m) = write_atomically(std::path::Path::new(path), &r.status()) {
+            return Err(format!("could not write status to {}: {}", path, m));
+        }
     }
     r.close()
 }
 
+#[derive(Debug, Default)]
+struct Options {
+    align: bool,
+    anchor_nearest: bool,
+    badge: Option<String>,
+    before: Option<(String, i64)>,
+    case_insensitive: bool,
+    daily_from: Option<NaiveDate>,
+    daily_to: Option<NaiveDate>,
+    day_rollover_hour: i64,
+    default_command: DefaultCommand,
+    details: bool,
+    dry_run: bool,
+    ephemeral: bool,
+    epoch_day: bool,
+    group_by_tag: bool,
+    include_past: bool,
+    legacy: bool,
+    history: bool,
+    interval: u64,
+    nearest: bool,
+    preview_purge: bool,
+    priority: Option<u32>,
+    new_only: bool,
+    no_summary: bool,
+    output: Option<String>,
+    past_days: i64,
+    quiet: bool,
+    recurrence_label: bool,
+    reset_seen: bool,
+    reverse: bool,
+    sort_by_priority: bool,
+    sort_ties: SortTies,
+    status_file: Option<String>,
+    two_digit_pivot: i32,
+    two_digit_years: bool,
+    verbose: bool,
+    watch: bool,
+    window: Option<(NaiveDate, i64)>,
+    wrap: Option<usize>,
+    yes: bool,
+}
+
+impl Options {
+    // reads defaults from the config file, then removes the leading --options from args
+    fn parse(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut options = Options { history: true, interval: 60, two_digit_pivot: 70, ..Options::default() };
+        for (key, value) in read_config()? {
+            match key.as_str() {
+                "two_digit_years" => options.two_digit_pivot = parse_pivot(&value)?,
+                "sort_ties" => options.sort_ties = SortTies::parse(&value)?,
+                "default_command" => options.default_command = DefaultCommand::parse(value.trim_matches('"'))?,
+                "day_rollover_hour" => options.day_rollover_hour = match value.parse::<i64>() {
+                    Ok(hour) if (0..24).contains(&hour) => hour,
+                    _ => return Err(format!("day_rollover_hour must be an hour from 0 to 23, not {}", value))
+                },
+                "history" => options.history = parse_switch(&key, &value)?,
+                "expand_recurrence_label" => options.recurrence_label = parse_switch(&key, &value)?,
+                _ => ()
+            }
+        }
+        while args.first().is_some_and(|arg| arg.starts_with("--")) {
+            let flag = args.remove(0);
+            if flag == "--" {
+                break;
+            }
+            options.parse_flag(&flag, args)?;
+        }
+        // options may also follow a command, up to a --
+        if args.first().is_some_and(|arg| COMMANDS.contains(&arg.as_str())) {
+            let mut rest = args.split_off(1);
+            while !rest.is_empty() {
+                let arg = rest.remove(0);
+                if arg == "--" {
+                    args.append(&mut rest);
+                } else if arg.starts_with("--") && !COMMAND_ARGS.contains(&arg.as_str()) {
+                    options.parse_flag(&arg, &mut rest)?;
+                } else {
+                    args.push(arg);
+                }
+            }
+        }
+        if options.include_past && options.past_days == 0 {
+            options.past_days = options.window.map_or(7, |(_, ndays)| ndays);
+        }
+        // the rest of the listing options are ignored by legacy_listing
+        if options.legacy {
+            options.anchor_nearest = false;
+            options.day_rollover_hour = 0;
+            options.past_days = 0;
+            options.sort_ties = SortTies::Message;
+        }
+        Ok(options)
+    }
+    // one option, taking any value it has from the front of args
+    fn parse_flag(&mut self, flag: &str, args: &mut Vec<String>) -> Result<(), String> {
+        match flag {
+            "--align" => self.align = true,
+            "--anchor" => self.anchor_nearest = match option_value(args, "--anchor")?.as_str() {
+                "nearest" => true,
+                "next" => false,
+                anchor => return Err(format!("anchor must be next or nearest, not {}", anchor))
+            },
+            "--badge" => if self.badge.is_none() {
+                self.badge = Some("[{n}]".to_string());
+            },
+            "--before" => {
+                let referent = option_value(args, "--before")?;
+                let days = match option_value(args, "--before")?.parse::<i64>() {
+                    Ok(days) if days >= 0 => days,
+                    _ => return Err("usage: remind --before reminder days message".to_string())
+                };
+                self.before = Some((referent, days));
+            }
+            "--case-insensitive" => self.case_insensitive = true,
+            "--badge-format" => self.badge = Some(option_value(args, "--badge-format")?),
+            "--daily-from" => self.daily_from = Some(parse_date(&option_value(args, "--daily-from")?)?),
+            "--daily-to" => self.daily_to = Some(parse_date(&option_value(args, "--daily-to")?)?),
+            "--details" => self.details = true,
+            "--dry-run" => self.dry_run = true,
+            "--ephemeral" => self.ephemeral = true,
+            "--format" => self.epoch_day = match option_value(args, "--format")?.as_str() {
+                "iso" => false,
+                "epoch-day" => true,
+                format => return Err(format!("format must be iso or epoch-day, not {}", format))
+            },
+            "--group-by-tag" => self.group_by_tag = true,
+            "--include-past" => self.include_past = true,
+            "--legacy" => self.legacy = true,
+            "--interval" => self.interval = match option_value(args, "--interval")?.parse::<u64>() {
+                Ok(seconds) if seconds > 0 => seconds,
+                _ => return Err("interval must be a number of seconds".to_string())
+            },
+            "--nearest" => self.nearest = true,
+            "--new-only" => self.new_only = true,
+            "--no-summary" => self.no_summary = true,
+            "--output" => self.output = Some(option_value(args, "--output")?),
+            "--past-days" => self.past_days = match option_value(args, "--past-days")?.parse::<i64>() {
+                Ok(days) if (0..=MAX_DAILY).contains(&days) => days,
+                _ => return Err(format!("past days must be a number from 0 to {}", MAX_DAILY))
+            },
+            "--preview-purge" => self.preview_purge = true,
+            "--quiet" => self.quiet = true,
+            "--reset-seen" => self.reset_seen = true,
+            "--reverse" => self.reverse = true,
+            "--priority" => self.priority = match option_value(args, "--priority")?.parse::<u32>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => return Err("priority must be a number from 1 up".to_string())
+            },
+            "--sort" => self.sort_by_priority = match option_value(args, "--sort")?.as_str() {
+                "priority" => true,
+                "date" => false,
+                sort => return Err(format!("sort must be priority or date, not {}", sort))
+            },
+            "--sort-ties" => self.sort_ties = SortTies::parse(&option_value(args, "--sort-ties")?)?,
+            "--status-file" => self.status_file = Some(option_value(args, "--status-file")?),
+            "--two-digit-years" => {
+                self.two_digit_years = true;
+                if let Some(pivot) = args.first().and_then(|arg| arg.strip_prefix("pivot=")).map(parse_pivot) {
+                    args.remove(0);
+                    self.two_digit_pivot = pivot?;
+                }
+            }
+            "--verbose" => self.verbose = true,
+            "--yes" => self.yes = true,
+            "--watch" => self.watch = true,
+            "--window" => self.window = Some(parse_window(&option_value(args, "--window")?)?),
+            "--wrap" => self.wrap = match option_value(args, "--wrap")?.parse::<usize>() {
+                Ok(columns) if columns > 0 => Some(columns),
+                _ => return Err("wrap must be a number of columns".to_string())
+            },
+            option => return Err(format!("unknown option {}", option)),
+        }
+        Ok(())
+    }
+}
+
+// what remind does when run with no arguments
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum DefaultCommand {
+    #[default]
+    Week,
+    Today,
+    List,
+    Next,
+}
+
+impl DefaultCommand {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "week" => Ok(DefaultCommand::Week),
+            "today" => Ok(DefaultCommand::Today),
+            "list" => Ok(DefaultCommand::List),
+            "next" => Ok(DefaultCommand::Next),
+            _ => Err(format!("default command must be week, today, list or next, not {}", value))
+        }
+    }
+}
+
+// how reminders on the same date and at the same time are ordered
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum SortTies {
+    #[default]
+    Message,
+    // as added, which with this is also how they're kept in the database
+    Order,
+    Priority,
+}
+
+impl SortTies {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "message" => Ok(SortTies::Message),
+            "order" => Ok(SortTies::Order),
+            "priority" => Ok(SortTies::Priority),
+            _ => Err(format!("sort ties must be order, message or priority, not {}", value))
+        }
+    }
+}
+
+// the reminders in another copy of the database, which unlike the database must exist
+fn load_copy(path: &str) -> Result<Vec<ReminderItem>, String> {
+    let path = match std::env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(e) => return Err(format!("could not find current directory: {}", e))
+    };
+    if !path.exists() {
+        return Err(format!("{} not found", path.display()));
+    }
+    let copy = Reminders::new(&path.to_string_lossy(), &Options::default(), false)?;
+    Ok(copy.reminder_items.into_iter().chain(copy.unresolved).collect())
+}
+
+// the name and birthday of each contact in a vCard file that has both
+fn vcard_birthdays(vcf: &str) -> Vec<(String, String)> {
+    // a line beginning with a space or tab continues the one before
+    let mut lines: Vec<String> = vec!();
+    for line in vcf.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string())
+        }
+    }
+    let mut birthdays = vec!();
+    let (mut name, mut bday) = (None, None);
+    for line in lines {
+        let (property, value) = match line.split_once(':') {
+            Some((property, value)) => (property.split(';').next().unwrap_or_default().to_uppercase(), value.trim()),
+            None => continue
+        };
+        match property.as_str() {
+            "BEGIN" => {
+                name = None;
+                bday = None;
+            }
+            "FN" => name = Some(value.replace("\\,", ",").replace("\\;", ";")),
+            "BDAY" => bday = Some(value.to_string()),
+            "END" => if let (Some(name), Some(bday)) = (name.take(), bday.take()) {
+                birthdays.push((name, bday));
+            },
+            _ => ()
+        }
+    }
+    birthdays
+}
+
+// the month and day of a vCard birthday, with or without a year
+fn parse_bday(bday: &str) -> Option<(u32, u32)> {
+    let date = bday.split('T').next().unwrap_or_default().replace('-', "");
+    let digits = if bday.starts_with("--") {
+        date.as_str()
+    } else if date.len() == 8 {
+        &date[4..]
+    } else {
+        return None;
+    };
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (month, day) = (digits[..2].parse().ok()?, digits[2..].parse().ok()?);
+    NaiveDate::from_ymd_opt(2000, month, day).map(|_| (month, day))
+}
+
+// replaces {year}, and {year-n} or {year+n}, with the year or the year n before or after
+fn expand_years(message: &str, year: i32) -> String {
+    let mut expanded = String::new();
+    let mut rest = message;
+    while let Some(start) = rest.find("{year") {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break
+        };
+        let offset = &rest["{year".len()..end];
+        let digits = |n: &str| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit());
+        let n = match (offset.strip_prefix('+'), offset.strip_prefix('-')) {
+            _ if offset.is_empty() => Some(0),
+            (Some(n), _) if digits(n) => n.parse::<i32>().ok(),
+            (_, Some(n)) if digits(n) => n.parse::<i32>().ok().map(|n| -n),
+            _ => None
+        };
+        match n.and_then(|n| year.checked_add(n)) {
+            Some(year) => expanded.push_str(&year.to_string()),
+            None => expanded.push_str(&rest[..=end])
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded + rest
+}
+
+// a word such as !1 at the start of a message
+fn parse_priority(word: &str) -> Option<u32> {
+    word.strip_prefix('!')
+        .filter(|n| n.chars().all(|c| c.is_ascii_digit()))
+        .and_then(|n| n.parse::<u32>().ok())
+        .filter(|&n| n > 0)
+}
+
+// the words of text in lines of at most width characters
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec!();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.chars().collect::<Vec<_>>();
+        let len = line.chars().count();
+        if len > 0 && len + 1 + word.len() <= width {
+            line.push(' ');
+        } else if len > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect());
+        }
+        line.extend(word);
+    }
+    lines.push(line);
+    lines
+}
+
+// a message trimmed, single-spaced and lowercase, for comparing ignoring case and spacing
+fn normalized(message: &str) -> String {
+    message.split_whitespace().join(" ").to_lowercase()
+}
+
+fn last_day_of_month(year: i32, month: u32) -> u32 {
+    let (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).and_then(|date| date.pred_opt()).map_or(31, |date| date.day())
+}
+
+// a config setting that's on or off
+fn parse_switch(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" => Ok(true),
+        "off" | "false" => Ok(false),
+        _ => Err(format!("{} must be on or off, not {}", key, value))
+    }
+}
+
+fn plural(n: usize, word: &str) -> String {
+    format!("{} {}{}", n, word, if n == 1 { "" } else { "s" })
+}
+
+// the database is read afresh every interval, which also moves today on at midnight
+fn watch(options: &Options) -> Result<(), String> {
+    use std::io::Write;
+    let mut announced = std::collections::HashSet::new();
+    loop {
+        match Reminders::new(".reminders", options, false) {
+            Ok(r) => {
+                for line in r.newly_due(&mut announced) {
+                    println!("{}", line);
+                }
+                let _ = std::io::stdout().flush();
+            }
+            Err(e) => eprintln!("{}", e)
+        }
+        std::thread::sleep(std::time::Duration::from_secs(options.interval));
+    }
+}
+
+// explains how to add a reminder, the first time remind is run with no database, and says if it did
+fn onboard(marker: Option<std::path::PathBuf>) -> bool {
+    let marker = match marker {
+        Some(marker) if !marker.exists() => marker,
+        _ => return false
+    };
+    eprintln!("You have no reminders yet. Add one with\n\n    remind [year] month day message\n\n\
+        for instance \"remind 7 4 Independence Day\" for every year, or\n\
+        \"remind 2030 7 2 lunch with Pat\" for just once. Run remind with\n\
+        no arguments to see the reminders for the next seven days.\n");
+    if let Some(dir) = marker.parent() {
+        let _ = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&marker, ""));
+    }
+    true
+}
+
+// the command that opens url in the default browser
+fn opener(url: &str) -> std::process::Command {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/c", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    command.arg(url);
+    command
+}
+
+// writes a temporary file and renames it, so readers see either the old contents or the new
+fn write_atomically(path: &std::path::Path, data: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp, data).and_then(|_| std::fs::rename(&tmp, path))
+}
+
+fn seen_path() -> Option<std::path::PathBuf> {
+    dirs::state_dir().or_else(dirs::data_local_dir).map(|dir| dir.join("remind").join("seen"))
+}
+
+// 64-bit FNV-1a, which unlike std's hasher is stable from one build to the next
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+// the arguments a command takes, as in the usage
+fn command_usage(command: &str) -> &str {
+    match command {
+        "archive" => "archive list year",
+        "explain" => "explain reminder",
+        "export" => "export --report",
+        "import" => "import --vcf file",
+        "next-date" => "next-date reminder",
+        "note" => "note reminder [note]",
+        "open" => "open reminder",
+        "rename-tag" => "rename-tag old new",
+        "shift" => "shift offset",
+        "show" => "show reminder",
+        "sync-merge" => "sync-merge base theirs",
+        "tag" => "tag add|remove tag reminder",
+        // doctor, history, info, repair, review and rotate take no arguments
+        command => command
+    }
+}
+
+fn option_value(args: &mut Vec<String>, option: &str) -> Result<String, String> {
+    if args.is_empty() {
+        return Err(format!("option {} needs a value", option));
+    }
+    Ok(args.remove(0))
+}
+
+// to the --output file, if there is one, instead of standard output
+fn write_output(output: &str, path: Option<&str>) -> Result<(), String> {
+    match path {
+        Some(path) => if let Err(m) = std::fs::write(path, output) {
+            return Err(format!("could not write output to {}: {}", path, m));
+        },
+        None => print!("{}", output)
+    }
+    Ok(())
+}
+
+// a number of days from an offset like +7d, -7d or +2w
+fn parse_offset(value: &str) -> Result<i64, String> {
+    let error = || format!("offset must be like +7d, -7d or +2w, not {}", value);
+    let (n, days) = match value.char_indices().last() {
+        Some((i, 'd')) => (&value[..i], 1),
+        Some((i, 'w')) => (&value[..i], 7),
+        _ => return Err(error())
+    };
+    match n.strip_prefix('+').unwrap_or(n).parse::<i64>() {
+        Ok(n) if n.abs() < 100000 => Ok(n * days),
+        _ => Err(error())
+    }
+}
+
+// asks a yes or no question on the terminal
+fn confirm(question: &str) -> Result<bool, String> {
+    use std::io::Write;
+    print!("{}", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(_) => Ok(matches!(answer.trim(), "y" | "Y" | "yes")),
+        Err(e) => Err(format!("could not read answer: {}", e))
+    }
+}
+
+// the day it is at now, which lasts until rollover_hour of the next morning
+fn today_at(now: NaiveDateTime, rollover_hour: i64) -> NaiveDate {
+    (now - chrono::Duration::hours(rollover_hour)).date()
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| format!("date must be like 2025-12-01, not {}", value))
+}
+
+fn parse_window(value: &str) -> Result<(NaiveDate, i64), String> {
+    let window = value.split_once(':').and_then(|(start, ndays)| {
+        Some((parse_date(start).ok()?, ndays.parse::<i64>().ok().filter(|&n| n > 0)?))
+    });
+    window.ok_or_else(|| format!("window must be start:days, like 2025-12-01:7, not {}", value))
+}
+
+fn parse_pivot(value: &str) -> Result<i32, String> {
+    match value.trim_start_matches("pivot=").parse::<i32>() {
+        Ok(pivot) if (0..=99).contains(&pivot) => Ok(pivot),
+        _ => Err(format!("two-digit year pivot must be 0 to 99, not {}", value)),
+    }
+}
+
+fn config_path(name: &str) -> Option<std::path::PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("remind").join(name))
+}
+
+// "key = value" lines from the config file, if there is one
+fn read_config() -> Result<Vec<(String, String)>, String> {
+    let path = match config_path("config") {
+        Some(path) => path,
+        None => return Ok(vec!())
+    };
+    let data = match std::fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec!()),
+        Err(e) => return Err(format!("could not read config {}: {}", path.display(), e))
+    };
+    data.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| match l.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+            None => Err(format!("bad line in config {}: {}", path.display(), l))
+        })
+        .collect()
+}
+
+// a date on which a reminder occurs
+type Occurrence<'a> = (NaiveDate, &'a ReminderItem);
+
 #[derive(Debug)]
 struct Reminders {
     path: std::path::PathBuf,
+    ephemeral: bool,
+    format: u32,
     today: NaiveDate,
     reminder_items: Vec<ReminderItem>,
+    purged: Vec<ReminderItem>,
+    // changes for the log, purges included, in the order they're made
+    history: Vec<String>,
+    log_history: bool,
+    sort_ties: SortTies,
+    // for reminders added by the user without one
+    priority: Option<u32>,
+    // days before today of the reminders kept for listing rather than purged
+    past_days: i64,
+    // yearly reminders are listed at the occurrence nearest the window rather than the next
+    anchor_nearest: bool,
+    // reminders are left in the order they're read and added, as the original remind listed them
+    legacy: bool,
+    // before reminders whose other reminder can't be found
+    unresolved: Vec<ReminderItem>,
+    repairs: Repairs,
 }
 
-#[derive(Debug)]
+// what loading the database for repair cleaned up
+#[derive(Debug, Default)]
+struct Repairs {
+    blank: usize,
+    duplicates: usize,
+    reformatted: usize,
+    // lines whose two-digit years --two-digit-years expanded, as "old -> new"
+    years: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct ReminderItem {
     date: NaiveDate,
     recurring: bool,
+    cron: Option<cron::Schedule>,
     message: String,
+    url: Option<String>,
+    note: Option<String>,
+    // days before the reminder picked by the text
+    before: Option<(i64, String)>,
+    // 1 is the highest
+    priority: Option<u32>,
 }
 
 impl Reminders {
-    fn new(path_str: &str) -> Result<Self, String> {
-        let mut path = match dirs::home_dir() {
-            Some(dir) => dir,
-            None => return Err("could not find home directory!".to_string())
-        };
-        path.push(path_str);
-        let mut reminder = Reminders { path, today: Local::today().naive_local(), reminder_items: vec!() };
-        if let Ok(data) = std::fs::read_to_string(&reminder.path) {
-            for line in data.split("\n").filter(|&l| l != "") {
-                reminder.add(reminder.parse_item(line.split(" ").collect::<Vec<_>>().into_iter())?);
+    // to repair the database, loading tolerates stray spaces and drops duplicates
+    fn new(path_str: &str, options: &Options, repair: bool) -> Result<Self, String> {
+        let mut path = if options.ephemeral {
+            std::env::temp_dir()
+        } else {
+            match dirs::home_dir() {
+                Some(dir) => dir,
+                None => return Err("could not find home directory!".to_string())
             }
+        };
+        if options.ephemeral {
+            path.push(format!("remind-{}{}", std::process::id(), path_str));
+        } else {
+            path.push(path_str);
+        }
+        let today = today_at(Local::now().naive_local(), options.day_rollover_hour);
+        let mut reminder = Reminders::empty(path, today, options);
+        // a damaged database is an error, rather than an empty one to be written over
+        let data = match std::fs::read(&reminder.path) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(data) => data,
+                Err(e) => return Err(format!("{} is not valid UTF-8 (at byte {}); not changing it",
+                    reminder.path.display(), e.utf8_error().valid_up_to()))
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("could not read reminders from {}: {}", reminder.path.display(), e))
+        };
+        reminder.load(&data, options, repair)?;
+        for warning in reminder.resolve() {
+            eprintln!("{}", warning);
         }
         Ok(reminder)
     }
-    fn add(&mut self, item: ReminderItem) {
-        if item.date >= self.today {
-            self.reminder_items.push(item);
-            self.reminder_items.sort_unstable_by_key(|item| item.date);
+    fn empty(path: std::path::PathBuf, today: NaiveDate, options: &Options) -> Self {
+        Reminders {
+            path,
+            ephemeral: options.ephemeral,
+            format: 1,
+            today,
+            reminder_items: vec!(),
+            purged: vec!(),
+            history: vec!(),
+            log_history: options.history && !options.ephemeral,
+            sort_ties: options.sort_ties,
+            priority: options.priority,
+            past_days: options.past_days,
+            anchor_nearest: options.anchor_nearest,
+            legacy: options.legacy,
+            unresolved: vec!(),
+            repairs: Repairs::default()
         }
     }
-    fn stringify(&self, ndays: i64) -> String {
-        let max_day = self.today + chrono::Duration::days(ndays);
-        self.reminder_items
-            .iter()
-            .filter(|item| ndays == 0 || item.date < max_day)
-            .map(|i| i.to_string() + "\n")
-            .join("")
-    }
-    fn close(self) -> Result<(), String> {
-        match std::fs::write(&self.path, self.stringify(0)) {
-            Err(m) => Err(format!("could not write reminders to {}: {}", self.path.display(), m)),
-            _ => Ok(())
+    // adds the reminders in data, the contents of the database
+    fn load(&mut self, data: &str, options: &Options, repair: bool) -> Result<(), String> {
+        let mut lines = data.strip_suffix('\n').unwrap_or(data).split('\n').peekable();
+        if let Some(version) = lines.peek().and_then(|l| l.strip_prefix(FORMAT_HEADER)) {
+            self.format = match version.trim().parse::<u32>() {
+                Ok(version) => version,
+                _ => return Err(format!("bad format header in {}: {}", self.path.display(), version))
+            };
+            lines.next();
+        }
+        for line in lines {
+            if line.trim().is_empty() {
+                if repair && !data.is_empty() {
+                    self.repairs.blank += 1;
+                }
+                continue;
+            }
+            let mut fields = if repair {
+                line.split_whitespace().map(|f| f.to_string()).collect::<Vec<_>>()
+            } else {
+                line.split(" ").map(|f| f.to_string()).collect::<Vec<_>>()
+            };
+            if options.two_digit_years && expand_two_digit_year(&mut fields, options.two_digit_pivot) {
+                self.repairs.years.push(format!("{} -> {}", line, fields.join(" ")));
+            }
+            match self.parse_item(fields.into_iter(), false) {
+                Ok(item) if repair && self.is_duplicate(&item, options.case_insensitive) => self.repairs.duplicates += 1,
+                Ok(item) => {
+                    if repair && item.to_string() != line && item.date >= self.today {
+                        self.repairs.reformatted += 1;
+                    }
+                    self.add(item);
+                }
+                // list what we can of a newer format
+                Err(_) if self.format > FORMAT_VERSION => (),
+                Err(e) => return Err(e)
+            }
         }
+        Ok(())
     }
-    fn parse_item<I, T>(&self, mut args: I) -> Result<ReminderItem, String>
-    where I: Iterator<Item=T> + ExactSizeIterator,
-        T: std::fmt::Display,
-    {
-        let usage = Err("usage: remind [year] month day message".to_string());
-        let mut arg = args.next();
-        let year = match &arg {
-            Some(year) => {
-                match year.to_string().parse::<i32>() {
-                    Ok(year) if year > 99 => {
-                        arg = args.next();
-                        Some(year)
+    // dates the before reminders from the reminders they refer to
+    fn resolve(&mut self) -> Vec<String> {
+        let mut warnings = vec!();
+        let mut resolved = vec!();
+        for item in &self.reminder_items {
+            if let Some((days, referent)) = &item.before {
+                match self.referent_date(referent, *days) {
+                    Ok(date) => resolved.push(Some(date)),
+                    Err(e) => {
+                        warnings.push(format!("not listing \"{}\": {}", item.message, e));
+                        resolved.push(None);
                     }
-                    Ok(_) => None,
-                    _ => return usage
                 }
             }
-            None => return usage
-        };
-        if args.len() < 2 {
-            return usage;
         }
-        let month = match arg.unwrap().to_string().parse::<u32>() {
-            Ok(month) => month,
-            _ => return usage
+        let mut resolved = resolved.into_iter();
+        for mut item in std::mem::take(&mut self.reminder_items) {
+            if item.before.is_some() {
+                match resolved.next().unwrap() {
+                    Some((date, recurring)) => {
+                        item.date = date;
+                        item.recurring = recurring;
+                    }
+                    None => {
+                        self.unresolved.push(item);
+                        continue;
+                    }
+                }
+            }
+            self.add(item);
+        }
+        warnings
+    }
+    // the date days before the next occurrence of referent that far away, and whether it recurs
+    fn referent_date(&self, referent: &str, days: i64) -> Result<(NaiveDate, bool), String> {
+        let matches = self.select(referent)
+            .into_iter()
+            .filter(|&i| self.reminder_items[i].before.is_none())
+            .collect::<Vec<_>>();
+        // a one-off copy of a recurring reminder, as review adds when snoozing it, isn't another match
+        let is_copy = |i: usize| {
+            let item = &self.reminder_items[i];
+            !item.recurring && matches.iter().any(|&j| self.reminder_items[j].recurring && self.reminder_items[j].message == item.message)
         };
-        let day = match args.next().unwrap().to_string().parse::<u32>() {
-            Ok(day) => day,
-            _ => return usage
+        let matches = matches.iter().copied().filter(|&i| !is_copy(i)).collect::<Vec<_>>();
+        let item = match matches.as_slice() {
+            [i] => &self.reminder_items[*i],
+            [] => return Err(format!("no reminder matches \"{}\"", referent)),
+            matches => return Err(format!("{} reminders match \"{}\"", matches.len(), referent))
         };
-
-        let date = if let Some(year) = year {
-            NaiveDate::from_ymd_opt(year, month, day)
-        } else {
-            self.next_recurring_date(month, day)
+        let offset = chrono::Duration::days(days);
+        let date = match &item.cron {
+            Some(schedule) => schedule.next(self.today + offset),
+            None if item.recurring => self.next_recurring_date(self.today + offset, item.date.month(), item.date.day()),
+            None => Some(item.date)
         };
-        if let Some(date) = date {
-            Ok(ReminderItem{ date, recurring: year.is_none(), message: args.join(" ") })
-        } else {
-            usage
+        match date {
+            Some(date) => Ok((date - offset, item.recurring)),
+            None => Err(format!("\"{}\" never occurs", item.message))
         }
     }
-    fn next_recurring_date(&self, month: u32, day: u32) -> Option<NaiveDate> {
-        let mut year = self.today.year();
-        if month == 2 && day == 29 {
-            loop {
+    fn add_before(&mut self, referent: &str, days: i64, message: String) -> Result<(), String> {
+        if message.is_empty() || referent.is_empty() || referent.contains('"') {
+            return Err("usage: remind --before reminder days message".to_string());
+        }
+        let (date, recurring) = self.referent_date(referent, days)?;
+        // a dated reminder too soon for it
+        if date < self.today {
+            let away = (date + chrono::Duration::days(days) - self.today).num_days().max(0);
+            return Err(format!("\"{}\" is only {} away", referent, plural(away as usize, "day")));
+        }
+        let item = ReminderItem::new(date, recurring, None, message);
+        self.add_new(ReminderItem{ before: Some((days, referent.to_string())), ..item });
+        Ok(())
+    }
+    // a reminder for each day from from to to, none of them past
+    fn add_daily(&mut self, from: Option<NaiveDate>, to: Option<NaiveDate>, message: String) -> Result<(), String> {
+        let (from, to) = match (from, to) {
+            (Some(from), Some(to)) if from <= to => (from, to),
+            _ => return Err("usage: remind --daily-from start --daily-to end message".to_string())
+        };
+        if (to - from).num_days() >= MAX_DAILY {
+            return Err(format!("--daily-from to --daily-to spans more than {} days", MAX_DAILY));
+        }
+        if to < self.today {
+            return Err(format!("--daily-to {} is past", to));
+        }
+        for date in std::cmp::max(from, self.today).iter_days().take_while(|&date| date <= to) {
+            self.add_new(ReminderItem::new(date, false, None, message.clone()));
+        }
+        Ok(())
+    }
+    // adds a reminder given by the user rather than read from the database
+    fn add_new(&mut self, mut item: ReminderItem) {
+        item.priority = item.priority.or(self.priority);
+        self.history.push(format!("add {}", item));
+        self.add(item);
+    }
+    // with case_insensitive, reminders differing only in the case and spacing of their messages are the same
+    fn is_duplicate(&self, item: &ReminderItem, case_insensitive: bool) -> bool {
+        if !case_insensitive {
+            return self.reminder_items.contains(item);
+        }
+        let item = item.folded();
+        self.reminder_items.iter().any(|other| other.folded() == item)
+    }
+    // past reminders are purged, cron ones being kept at their next date
+    fn is_past(&self, item: &ReminderItem) -> bool {
+        item.date < self.today - chrono::Duration::days(self.past_days)
+    }
+    fn add(&mut self, item: ReminderItem) {
+        if !self.is_past(&item) {
+            self.reminder_items.push(item);
+            self.sort();
+        } else {
+            self.history.push(format!("purge {}", item));
+            self.purged.push(item);
+        }
+    }
+    fn sort(&mut self) {
+        if self.legacy {
+            return;
+        }
+        let ties = self.sort_ties;
+        self.reminder_items.sort_by(|item, other| item.date.cmp(&other.date).then_with(|| item.cmp_same_day(other, ties)));
+    }
+    // each date in the ndays from today on which a reminder occurs, in date order
+    fn upcoming(&self, ndays: i64) -> Vec<Occurrence<'_>> {
+        self.occurrences(self.today, ndays)
+    }
+    // the next occurrence of every reminder, in date order
+    fn all_occurrences(&self) -> Vec<Occurrence<'_>> {
+        self.reminder_items.iter().map(|item| (item.date, item)).collect()
+    }
+    // each date in the ndays from start on which a reminder occurs, in date order
+    fn occurrences(&self, start: NaiveDate, ndays: i64) -> Vec<Occurrence<'_>> {
+        let max_day = start + chrono::Duration::days(ndays);
+        let mut occurrences = vec!();
+        for item in &self.reminder_items {
+            match &item.cron {
+                Some(schedule) => occurrences.extend(
+                    std::cmp::max(start, self.today - chrono::Duration::days(self.past_days))
+                        .iter_days()
+                        .take_while(|&date| date < max_day)
+                        .filter(|&date| schedule.matches(date))
+                        .map(|date| (date, item))
+                ),
+                None => {
+                    let date = if item.recurring && self.anchor_nearest {
+                        // the next occurrence, if the nearest is before the window
+                        let anchor = std::cmp::max(start, self.today);
+                        let (month, day) = (item.date.month(), item.date.day());
+                        match self.nearest_recurring_date(anchor, month, day) {
+                            Some(date) if date < start => self.next_recurring_date(anchor, month, day),
+                            date => date
+                        }.unwrap_or(item.date)
+                    } else if item.recurring && start > self.today {
+                        self.next_recurring_date(start, item.date.month(), item.date.day()).unwrap_or(item.date)
+                    } else {
+                        item.date
+                    };
+                    if date >= start && date < max_day {
+                        occurrences.push((date, item));
+                    }
+                }
+            }
+        }
+        occurrences.sort_by(|(date, item), (other_date, other)| date.cmp(other_date).then_with(|| item.cmp_same_day(other, self.sort_ties)));
+        occurrences
+    }
+    // the database as close writes it: every reminder that isn't past, with the header if it's needed
+    fn persistable_lines(&self) -> String {
+        let items = self.reminder_items.iter().chain(&self.unresolved).filter(|item| !self.is_past(item)).collect::<Vec<_>>();
+        let lines = items.iter().map(|item| format!("{}\n", item)).join("");
+        if self.has_header() {
+            format!("{}{}\n{}", FORMAT_HEADER, FORMAT_VERSION, lines)
+        } else {
+            lines
+        }
+    }
+    // whether the database is written with the format header, which only cron and before reminders need
+    fn has_header(&self) -> bool {
+        self.reminder_items.iter().chain(&self.unresolved).any(|item| !self.is_past(item) && (item.cron.is_some() || item.before.is_some()))
+    }
+    // the next week as the original remind listed it, in database order, month and day for recurring reminders
+    fn legacy_listing(&self) -> String {
+        let week = self.today.iter_days().take(7).collect::<Vec<_>>();
+        self.reminder_items
+            .iter()
+            .flat_map(|item| week.iter().filter(move |&&date| match &item.cron {
+                Some(schedule) => schedule.matches(date),
+                None => date == item.date
+            }).map(move |date| (date, item)))
+            .map(|(date, item)| if item.recurring || item.cron.is_some() {
+                format!("{} {} {}\n", date.month(), date.day(), item.message)
+            } else {
+                format!("{} {} {} {}\n", date.year(), date.month(), date.day(), item.message)
+            })
+            .join("")
+    }
+    // the past reminders that close would delete, as written in the database
+    fn preview_purge(&self) -> String {
+        self.purged.iter().map(|item| format!("{}\n", item)).join("")
+    }
+    // the listing shown when remind is run with no arguments
+    fn listing(&self, options: &Options) -> Result<String, String> {
+        let (start, ndays) = options.window.unwrap_or((self.today, 7));
+        let (start, ndays) = (start - chrono::Duration::days(self.past_days), ndays + self.past_days);
+        let mut occurrences = self.occurrences(start, ndays);
+        if options.new_only {
+            occurrences = self.unseen(occurrences, seen_path())?;
+        }
+        if options.sort_by_priority {
+            occurrences.sort_by_key(|(_, item)| item.priority_rank());
+        }
+        let mut listing = if options.group_by_tag {
+            self.list_by_tag(&occurrences, options)
+        } else {
+            self.list(&occurrences, options)
+        };
+        if !(options.no_summary || options.quiet && occurrences.is_empty()) {
+            listing += &self.summary(&occurrences, start, ndays);
+        }
+        Ok(listing)
+    }
+    // what remind shows when run with no arguments, as default_command has it
+    fn default_listing(&self, options: &mut Options) -> Result<String, String> {
+        match options.default_command {
+            DefaultCommand::Week => self.listing(options),
+            DefaultCommand::Today => {
+                options.window = options.window.or(Some((self.today, 1)));
+                self.listing(options)
+            }
+            DefaultCommand::List => Ok(self.list(&self.all_occurrences(), options)),
+            DefaultCommand::Next => Ok(self.list(&self.all_occurrences().into_iter().take(1).collect::<Vec<_>>(), options))
+        }
+    }
+    // the dates in the next year on which different reminders give the same message
+    fn doctor(&self) -> String {
+        let collisions = self.occurrences(self.today, MAX_DAILY)
+            .into_iter()
+            .filter(|(_, item)| item.recurring || item.cron.is_some())
+            .into_group_map_by(|(date, item)| (*date, normalized(&item.message)))
+            .into_iter()
+            .filter(|(_, occurrences)| occurrences.len() > 1)
+            .sorted()
+            .collect::<Vec<_>>();
+        let mut report = String::new();
+        for ((date, _), occurrences) in &collisions {
+            report += &format!("{}: {} with the same message\n", date, plural(occurrences.len(), "reminder"));
+            for (_, item) in occurrences {
+                report += &format!("    {}\n", item);
+            }
+        }
+        report + &format!("{} found\n", plural(collisions.len(), "collision"))
+    }
+    fn info(&self) -> String {
+        let home = dirs::home_dir().map_or("(none)".to_string(), |home| home.display().to_string());
+        let tz = std::env::var("TZ").map(|tz| format!("{} ", tz)).unwrap_or_default();
+        let count = |f: fn(&&ReminderItem) -> bool| self.reminder_items.iter().filter(f).count();
+        format!("remind {}\ndatabase {} ({})\nformat {}\nhome {}\ntoday {} ({}{})\n\
+            {} yearly, {} dated, {} cron, {} before, {} unresolved, {} past\n",
+            env!("CARGO_PKG_VERSION"),
+            self.path.display(), if self.path.exists() { "present" } else { "missing" },
+            self.format, home, self.today, tz, Local::now().format("%:z"),
+            count(|item| item.recurring && item.cron.is_none() && item.before.is_none()),
+            count(|item| !item.recurring && item.cron.is_none() && item.before.is_none()),
+            count(|item| item.cron.is_some()),
+            count(|item| item.before.is_some()),
+            self.unresolved.len(), self.purged.len())
+    }
+    fn report(&self, options: &Options) -> String {
+        let (start, ndays) = options.window.unwrap_or((self.today, 7));
+        let upcoming = if start == self.today {
+            format!("Upcoming (next {} days)", ndays)
+        } else {
+            format!("Upcoming ({} days from {})", ndays, start)
+        };
+        let recurring = self.reminder_items
+            .iter()
+            .filter(|item| item.recurring || item.cron.is_some())
+            .sorted_by_key(|item| if item.cron.is_some() { (0, 0) } else { (item.date.month(), item.date.day()) })
+            .map(|item| match &item.cron {
+                Some(schedule) => format!("cron \"{}\" {}\n", schedule, item.marked(item.date)),
+                None => format!("{} {}\n", item.when(item.date), item.marked(item.date))
+            })
+            .join("");
+        let dated = self.reminder_items
+            .iter()
+            .filter(|item| !item.recurring && item.cron.is_none())
+            .map(|item| (item.date, item))
+            .collect::<Vec<_>>();
+        format!("{}\n{}\nRecurring events\n{}\nAll dated reminders\n{}",
+            upcoming, self.list(&self.occurrences(start, ndays), options), recurring, self.list(&dated, options))
+    }
+    fn list(&self, occurrences: &[Occurrence], options: &Options) -> String {
+        let mut occurrences = occurrences.to_vec();
+        if options.reverse {
+            occurrences.reverse();
+        }
+        let width = if options.align {
+            occurrences.iter().map(|(date, i)| i.when(*date).len()).max().unwrap_or(0)
+        } else {
+            0
+        };
+        occurrences
+            .iter()
+            .map(|(date, i)| {
+                let when = format!("{:<width$}", i.when(*date), width = width);
+                let message = match &i.url {
+                    Some(url) if options.verbose => format!("{} <{}>", i.marked(*date), url),
+                    _ => i.marked(*date)
+                };
+                let message = match (&i.cron, &i.before) {
+                    _ if !options.recurrence_label => message,
+                    (Some(schedule), _) => format!("{} (cron {})", message, schedule),
+                    (None, Some((days, referent))) => format!("{} ({} before {})", message, plural(*days as usize, "day"), referent),
+                    (None, None) if i.recurring => format!("{} (yearly)", message),
+                    (None, None) => message
+                };
+                let message = match options.wrap {
+                    Some(columns) => {
+                        let indent = when.chars().count() + 1;
+                        wrap(&message, columns.saturating_sub(indent).max(1)).join(&format!("\n{:indent$}", "", indent = indent))
+                    }
+                    None => message
+                };
+                match &i.note {
+                    Some(note) if options.details => format!("{} {}\n{:indent$}{}\n", when, message, "", note, indent = when.len() + 1),
+                    _ => format!("{} {}\n", when, message)
+                }
+            })
+            .join("")
+    }
+    // a reminder with several tags is listed under each
+    fn list_by_tag(&self, occurrences: &[Occurrence], options: &Options) -> String {
+        let tags = occurrences.iter().flat_map(|(_, item)| item.tags()).sorted().dedup().collect::<Vec<_>>();
+        let mut listing = String::new();
+        for tag in tags {
+            let tagged = occurrences.iter().filter(|(_, item)| item.tags().contains(&tag)).copied().collect::<Vec<_>>();
+            listing += &format!("#{}\n{}", tag, self.list(&tagged, options));
+        }
+        let untagged = occurrences.iter().filter(|(_, item)| item.tags().is_empty()).copied().collect::<Vec<_>>();
+        if !untagged.is_empty() {
+            listing += &format!("(untagged)\n{}", self.list(&untagged, options));
+        }
+        listing
+    }
+    fn summary(&self, occurrences: &[Occurrence], start: NaiveDate, ndays: i64) -> String {
+        let today = occurrences.iter().filter(|(date, _)| *date == self.today).count();
+        let mut summary = match occurrences.len() {
+            0 => "no reminders".to_string(),
+            1 => "1 reminder".to_string(),
+            n => format!("{} reminders", n)
+        };
+        if start == self.today && ndays == 1 {
+            return summary + " today\n";
+        } else if start == self.today {
+            summary += &format!(" in the next {} days", ndays);
+        } else {
+            summary += &format!(" in the {} days from {}", ndays, start);
+        }
+        if today > 0 {
+            summary += &format!(" ({} today)", today);
+        }
+        summary + "\n"
+    }
+    // drops the occurrences already shown today, and records the rest as shown in state
+    fn unseen<'a>(&self, occurrences: Vec<Occurrence<'a>>, state: Option<std::path::PathBuf>) -> Result<Vec<Occurrence<'a>>, String> {
+        let path = match state {
+            Some(path) => path,
+            None => return Err("could not find state directory!".to_string())
+        };
+        let data = std::fs::read_to_string(&path).unwrap_or_default();
+        let seen = data
+            .lines()
+            .filter_map(|l| l.split_once(' '))
+            .collect::<std::collections::HashMap<_, _>>();
+        let today = self.today.to_string();
+        let mut state = String::new();
+        let mut unseen = vec!();
+        for (date, item) in occurrences {
+            let hash = format!("{:016x}", fnv1a(&format!("{} {}", date, item)));
+            if seen.get(hash.as_str()) != Some(&today.as_str()) {
+                unseen.push((date, item));
+            }
+            // only occurrences still in the window are kept
+            state += &format!("{} {}\n", hash, today);
+        }
+        // so that shells starting together don't clash
+        let written = std::fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| write_atomically(&path, &state));
+        match written {
+            Err(m) => Err(format!("could not write {}: {}", path.display(), m)),
+            _ => Ok(unseen)
+        }
+    }
+    // the reminders due today that aren't yet in announced, which are added to it
+    fn newly_due(&self, announced: &mut std::collections::HashSet<String>) -> Vec<String> {
+        let today = self.today.to_string();
+        announced.retain(|key: &String| key.starts_with(&today));
+        self.upcoming(1)
+            .into_iter()
+            .filter(|(date, item)| announced.insert(format!("{} {}", date, item)))
+            .map(|(date, item)| format!("{} {}", item.when(date), item.marked(date)))
+            .collect()
+    }
+    // the reminder picked, with its link and note
+    fn show(&self, selector: &str) -> Result<String, String> {
+        let item = &self.reminder_items[self.select_one(selector)?];
+        let mut output = format!("{} {}\n", item.when(item.date), item.marked(item.date));
+        if let Some(url) = &item.url {
+            output += &format!("{}\n", url);
+        }
+        if let Some(note) = &item.note {
+            output += &format!("{}\n", note);
+        }
+        Ok(output)
+    }
+    // the date the reminder picked next occurs, as 2025-06-01 or with epoch_day as days since 1970-01-01
+    fn next_date(&self, selector: &str, epoch_day: bool) -> Result<String, String> {
+        let date = self.reminder_items[self.select_one(selector)?].date;
+        if epoch_day {
+            Ok(format!("{}\n", (date - NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days()))
+        } else {
+            Ok(format!("{}\n", date))
+        }
+    }
+    fn explain(&self, selector: &str, options: &Options) -> Result<String, String> {
+        let (start, ndays) = options.window.unwrap_or((self.today, 7));
+        let end = start + chrono::Duration::days(ndays - 1);
+        let i = match self.select_one(selector) {
+            Ok(i) => i,
+            Err(e) => {
+                let selector = selector.to_lowercase();
+                return match self.purged.iter().find(|item| item.message.to_lowercase().contains(&selector)) {
+                    Some(item) => Ok(format!("{}\nnot shown: {} is past, so the reminder is deleted\n", item, item.date)),
+                    None => Err(e)
+                };
+            }
+        };
+        let item = &self.reminder_items[i];
+        let days = (item.date - self.today).num_days();
+        let mut explanation = format!("{} {}\nnext occurs {}, {}\n", item.when(item.date), item.message, item.date, match days {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            n => format!("in {} days", n)
+        });
+        let shown = self.occurrences(start, ndays).iter().filter(|(_, other)| std::ptr::eq(*other, item)).count();
+        explanation += &match shown {
+            0 => format!("not shown: outside the window {} to {}\n", start, end),
+            n => format!("shown: {} in the window {} to {}\n", plural(n, "time"), start, end)
+        };
+        Ok(explanation)
+    }
+    fn status(&self) -> String {
+        let next = match self.all_occurrences().iter().map(|(date, _)| date).min() {
+            Some(date) => format!("\"{}\"", date),
+            None => "null".to_string()
+        };
+        format!("{{\"generated\": \"{}\", \"due\": {}, \"next\": {}}}\n",
+            Local::now().to_rfc3339_opts(SecondsFormat::Secs, false), self.upcoming(1).len(), next)
+    }
+    fn badge(&self, format: &str) -> String {
+        match self.upcoming(1).len() {
+            0 => String::new(),
+            n => format.replace("{n}", &n.to_string())
+        }
+    }
+    // a database in a newer format than this version of remind understands is never changed
+    fn writable(&self) -> Result<(), String> {
+        if self.format > FORMAT_VERSION {
+            return Err(format!("{} is in format {}, newer than this version of remind; not changing it",
+                self.path.display(), self.format));
+        }
+        Ok(())
+    }
+    fn close(self) -> Result<(), String> {
+        if self.format > FORMAT_VERSION {
+            return Ok(());
+        }
+        self.write_history();
+        if let Err(m) = std::fs::write(&self.path, self.persistable_lines()) {
+            return Err(format!("could not write reminders to {}: {}", self.path.display(), m));
+        }
+        if self.ephemeral {
+            let _ = std::fs::remove_file(&self.path);
+        }
+        Ok(())
+    }
+    // the database's path with suffix appended, as in .reminders.log
+    fn sibling_path(&self, suffix: &str) -> std::path::PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(suffix);
+        path.into()
+    }
+    // best effort: a failure is reported, but the database is still written
+    fn write_history(&self) {
+        use std::io::Write;
+        if !self.log_history || self.history.is_empty() {
+            return;
+        }
+        let now = Local::now().to_rfc3339_opts(SecondsFormat::Secs, false);
+        let entries = self.history.iter().map(|entry| format!("{} {}\n", now, entry)).join("");
+        let path = self.sibling_path(".log");
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut log| log.write_all(entries.as_bytes()));
+        if let Err(m) = written {
+            eprintln!("could not write history to {}: {}", path.display(), m);
+        }
+    }
+    fn read_history(&self) -> Result<String, String> {
+        let path = self.sibling_path(".log");
+        match std::fs::read_to_string(&path) {
+            Ok(history) => Ok(history),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(format!("could not read history from {}: {}", path.display(), e))
+        }
+    }
+    // moves the past reminders into an archive file for each year instead of purging them
+    fn rotate(&mut self) -> Result<String, String> {
+        use std::io::Write;
+        let years = self.purged.iter().into_group_map_by(|item| item.date.year());
+        for (year, items) in years.iter().sorted_by_key(|(&year, _)| year) {
+            let path = self.sibling_path(&format!(".{}", year));
+            let lines = items.iter().map(|item| format!("{}\n", item)).join("");
+            let written = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut archive| archive.write_all(lines.as_bytes()));
+            if let Err(m) = written {
+                return Err(format!("could not write archive {}: {}", path.display(), m));
+            }
+        }
+        let output = format!("archived {} in {}\n", plural(self.purged.len(), "reminder"), plural(years.len(), "file"));
+        // the past reminders are archived rather than purged
+        for entry in self.history.iter_mut() {
+            if let Some(item) = entry.strip_prefix("purge ") {
+                *entry = format!("archive {}", item);
+            }
+        }
+        self.purged.clear();
+        Ok(output)
+    }
+    fn read_archive(&self, year: &str) -> Result<String, String> {
+        let year = match year.parse::<i32>() {
+            Ok(year) if year > 99 => year,
+            _ => return Err("usage: remind archive list year".to_string())
+        };
+        let path = self.sibling_path(&format!(".{}", year));
+        match std::fs::read_to_string(&path) {
+            Ok(archive) => Ok(archive),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(format!("no archive for {}", year)),
+            Err(e) => Err(format!("could not read archive {}: {}", path.display(), e))
+        }
+    }
+    // a three-way merge of theirs into these reminders, both changed from base
+    fn sync_merge(&mut self, base: &[ReminderItem], theirs: &[ReminderItem], case_insensitive: bool) -> String {
+        // with case_insensitive, reminders differing only in the case and spacing of their messages are the same
+        let key = |item: &ReminderItem| if case_insensitive { item.folded().to_string() } else { item.to_string() };
+        let lines = |items: &[ReminderItem]| items.iter().map(key).collect::<std::collections::HashSet<_>>();
+        let ours = self.reminder_items.iter().chain(&self.unresolved).cloned().collect::<Vec<_>>();
+        let (base_lines, our_lines, their_lines) = (lines(base), lines(&ours), lines(theirs));
+        let same_reminder = |item: &ReminderItem, other: &ReminderItem|
+            (item.date, item.recurring, &item.cron, &item.before) == (other.date, other.recurring, &other.cron, &other.before);
+        // what a base reminder missing from a side became there, if it was changed rather than deleted
+        let changed = |item: &ReminderItem, side: &[ReminderItem]| {
+            let added = side.iter().filter(|other| !base_lines.contains(&key(other)) && same_reminder(item, other)).collect::<Vec<_>>();
+            if added.len() == 1 { Some(added[0].clone()) } else { None }
+        };
+        let mut output = String::new();
+        let mut conflicts = 0;
+        let mut skipped = std::collections::HashSet::new();
+        let mut deleted = std::collections::HashSet::new();
+        for item in base {
+            let line = key(item);
+            match (our_lines.contains(&line), their_lines.contains(&line)) {
+                (true, false) if changed(item, theirs).is_none() => {
+                    deleted.insert(line);
+                }
+                (false, false) => match (changed(item, &ours), changed(item, theirs)) {
+                    (Some(our), Some(their)) if our == their => (),
+                    (None, None) => (),
+                    (our, their) => {
+                        let show = |side: Option<ReminderItem>| side.map_or("deleted".to_string(), |item| item.to_string());
+                        if let Some(their) = &their {
+                            skipped.insert(key(their));
+                        }
+                        output += &format!("conflict: {}\n  ours: {}\n  theirs: {}\n", item, show(our), show(their));
+                        conflicts += 1;
+                    }
+                },
+                _ => ()
+            }
+        }
+        let (mut added, mut edited, mut removed) = (0, 0, 0);
+        // a change in theirs replaces our unchanged line
+        let mut replaced = std::collections::HashSet::new();
+        for item in base.iter().filter(|item| our_lines.contains(&key(item)) && !their_lines.contains(&key(item))) {
+            if let Some(their) = changed(item, theirs) {
+                skipped.insert(key(&their));
+                replaced.insert(key(item));
+                self.history.push(format!("edit {} -> {}", item, their));
+                self.add(their);
+                edited += 1;
+            }
+        }
+        self.reminder_items.retain(|item| !replaced.contains(&key(item)));
+        self.unresolved.retain(|item| !replaced.contains(&key(item)));
+        for item in theirs {
+            let line = key(item);
+            if !base_lines.contains(&line) && !our_lines.contains(&line) && !skipped.contains(&line) {
+                self.add_new(item.clone());
+                added += 1;
+            }
+        }
+        for item in self.reminder_items.iter().chain(&self.unresolved).filter(|item| deleted.contains(&key(item))) {
+            self.history.push(format!("delete {}", item));
+            removed += 1;
+        }
+        self.reminder_items.retain(|item| !deleted.contains(&key(item)));
+        self.unresolved.retain(|item| !deleted.contains(&key(item)));
+        output + &format!("{} added, {} changed, {} deleted, {}\n", added, edited, removed, plural(conflicts, "conflict"))
+    }
+    fn import_vcf(&mut self, vcf: &str) -> String {
+        let (mut imported, mut present, mut unreadable) = (0, 0, 0);
+        for (name, bday) in vcard_birthdays(vcf) {
+            let date = parse_bday(&bday).and_then(|(month, day)| self.next_recurring_date(self.today, month, day));
+            match date {
+                Some(date) => {
+                    let item = ReminderItem::new(date, true, None, format!("-- {} birthday", name));
+                    if self.is_duplicate(&item, false) {
+                        present += 1;
+                    } else {
+                        self.add_new(item);
+                        imported += 1;
+                    }
+                }
+                None => unreadable += 1
+            }
+        }
+        format!("imported {}, {} already present, {} unreadable\n", plural(imported, "birthday"), present, unreadable)
+    }
+    fn rename_tag(&mut self, from: &str, to: &str) -> Result<usize, String> {
+        let from = from.trim_start_matches('#');
+        let to = to.trim_start_matches('#');
+        if from.is_empty() || to.is_empty() || !to.chars().all(is_tag_char) {
+            return Err("usage: remind rename-tag old new".to_string());
+        }
+        let mut changed = 0;
+        for item in self.reminder_items.iter_mut() {
+            let mut message = String::new();
+            let mut last = 0;
+            for (start, end) in tag_spans(&item.message) {
+                if &item.message[start..end] == from {
+                    message.push_str(&item.message[last..start]);
+                    message.push_str(to);
+                    last = end;
+                }
+            }
+            if last > 0 {
+                let old = item.to_string();
+                message.push_str(&item.message[last..]);
+                item.message = message;
+                self.history.push(format!("edit {} -> {}", old, item));
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+    // adds or removes the tag on every reminder picked by the selector, returning how many changed
+    fn tag(&mut self, tag: &str, selector: &str, add: bool) -> Result<usize, String> {
+        let tag = tag.trim_start_matches('#');
+        if tag.is_empty() || !tag.chars().all(is_tag_char) {
+            return Err("usage: remind tag add|remove tag reminder".to_string());
+        }
+        let picked = self.select(selector);
+        if picked.is_empty() {
+            return Err(format!("no reminder matches \"{}\"", selector));
+        }
+        let mut changed = 0;
+        for i in picked {
+            let item = &mut self.reminder_items[i];
+            let has_tag = item.tags().contains(&tag);
+            let mut message = item.message.clone();
+            if add && !has_tag {
+                message = format!("{} #{}", message, tag);
+            } else if !add && has_tag {
+                for (start, end) in tag_spans(&item.message).into_iter().rev().filter(|&(start, end)| &item.message[start..end] == tag) {
+                    // with the space before it, or after it at the start
+                    let hash = start - 1;
+                    let (start, end) = match message[..hash].chars().last() {
+                        Some(space) => (hash - space.len_utf8(), end),
+                        None => (0, (end + 1).min(message.len()))
+                    };
+                    message.replace_range(start..end, "");
+                }
+            }
+            if message != item.message && !message.trim().is_empty() {
+                let old = item.to_string();
+                item.message = message;
+                self.history.push(format!("edit {} -> {}", old, item));
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+    // indexes of the reminders picked by a line number in the database, or by text in the message
+    fn select(&self, selector: &str) -> Vec<usize> {
+        let n = self.reminder_items.len();
+        match selector {
+            "--first" => return (0..n).take(1).collect(),
+            "--last" => return (0..n).skip(n.saturating_sub(1)).collect(),
+            _ => ()
+        }
+        if let Ok(n) = selector.parse::<usize>() {
+            // line numbers count the format header, when the database has one
+            let first = if self.has_header() { 2 } else { 1 };
+            return if (first..first + self.reminder_items.len()).contains(&n) { vec!(n - first) } else { vec!() };
+        }
+        let selector = selector.to_lowercase();
+        let matching = |exact: bool| (0..self.reminder_items.len())
+            .filter(|&i| {
+                let message = self.reminder_items[i].message.to_lowercase();
+                if exact { message == selector } else { message.contains(&selector) }
+            })
+            .collect::<Vec<_>>();
+        match matching(true) {
+            exact if exact.len() == 1 => exact,
+            _ => matching(false)
+        }
+    }
+    fn select_one(&self, selector: &str) -> Result<usize, String> {
+        match self.select(selector).as_slice() {
+            [i] => Ok(*i),
+            [] if self.reminder_items.is_empty() => Err("there are no reminders".to_string()),
+            [] => Err(format!("no reminder matches \"{}\"", selector)),
+            matches => Err(format!("{} reminders match \"{}\"", matches.len(), selector))
+        }
+    }
+    // the reminders moved by days, yearless reminders keeping to the dates they move to
+    fn shifted(&self, days: i64) -> Vec<ReminderItem> {
+        let offset = chrono::Duration::days(days);
+        self.reminder_items
+            .iter()
+            .map(|item| {
+                let date = match &item.cron {
+                    Some(_) => item.date,
+                    None if item.before.is_some() => item.date,
+                    None if item.recurring => {
+                        let moved = item.date + offset;
+                        self.next_recurring_date(self.today, moved.month(), moved.day()).unwrap_or(moved)
+                    }
+                    None => item.date + offset
+                };
+                ReminderItem{ date, ..item.clone() }
+            })
+            .collect()
+    }
+    // reads an action for each reminder due today from input, prompting on prompts
+    fn review(&mut self, input: impl std::io::BufRead, mut prompts: impl std::io::Write) -> Result<String, String> {
+        let due = (0..self.reminder_items.len())
+            .filter(|&i| self.reminder_items[i].date == self.today)
+            .collect::<Vec<_>>();
+        if due.is_empty() {
+            return Ok("nothing due today\n".to_string());
+        }
+        let mut lines = input.lines();
+        let mut read_line = |prompt: &str| {
+            let _ = write!(prompts, "{}", prompt);
+            let _ = prompts.flush();
+            match lines.next() {
+                Some(Ok(line)) => Ok(Some(line.trim().to_string())),
+                Some(Err(e)) => Err(format!("could not read input: {}", e)),
+                None => Ok(None)
+            }
+        };
+        let (mut kept, mut done, mut snoozed, mut edited) = (0, 0, 0, 0);
+        let mut history = vec!();
+        let mut deleted = vec!(false; self.reminder_items.len());
+        let mut copies = vec!();
+        'review: for (n, &i) in due.iter().enumerate() {
+            loop {
+                let item = &mut self.reminder_items[i];
+                let prompt = format!("({}/{}) {} {}\n[k]eep [d]one [s]nooze day [S]nooze week [e]dit [q]uit: ",
+                    n + 1, due.len(), item.when(self.today), item.message);
+                let days = match read_line(&prompt)?.as_deref() {
+                    Some("k") => {
+                        kept += 1;
+                        break;
+                    }
+                    Some("d") => {
+                        history.push(format!("delete {}", item));
+                        deleted[i] = true;
+                        done += 1;
+                        break;
+                    }
+                    Some("s") => 1,
+                    Some("S") => 7,
+                    Some("e") => {
+                        if let Some(text) = read_line("message: ")?.filter(|m| !m.is_empty()) {
+                            let old = item.to_string();
+                            // read as when adding a reminder, keeping what the edit doesn't give
+                            let edit = ReminderItem::new(item.date, item.recurring, item.cron.clone(), text);
+                            item.message = edit.message;
+                            item.priority = edit.priority.or(item.priority);
+                            item.url = edit.url.or(item.url.take());
+                            item.note = edit.note.or(item.note.take());
+                            history.push(format!("edit {} -> {}", old, item));
+                            edited += 1;
+                        }
+                        continue;
+                    }
+                    Some("q") | None => break 'review,
+                    _ => continue
+                };
+                let date = self.today + chrono::Duration::days(days);
+                // a before reminder is dated afresh on each run, so it's snoozed as a dated one
+                if item.recurring {
+                    let copy = ReminderItem{ date, recurring: false, cron: None, before: None, ..item.clone() };
+                    history.push(format!("add {}", copy));
+                    copies.push(copy);
+                } else {
+                    let old = item.to_string();
+                    item.date = date;
+                    item.before = None;
+                    history.push(format!("move {} -> {}", old, item));
+                }
+                snoozed += 1;
+                break;
+            }
+        }
+        let mut deleted = deleted.into_iter();
+        self.reminder_items.retain(|_| !deleted.next().unwrap_or(false));
+        self.reminder_items.append(&mut copies);
+        self.sort();
+        self.history.append(&mut history);
+        Ok(format!("{} kept, {} done, {} snoozed, {} edited\n", kept, done, snoozed, edited))
+    }
+    // with nearest, a day past the end of the month is moved back to its last day
+    fn parse_item<I, T>(&self, mut args: I, nearest: bool) -> Result<ReminderItem, String>
+    where I: Iterator<Item=T> + ExactSizeIterator,
+        T: std::fmt::Display,
+    {
+        let usage = Err("usage: remind [year] month day message".to_string());
+        let mut arg = args.next();
+        match arg.as_ref().map(|arg| arg.to_string()).as_deref() {
+            Some("cron") => return self.parse_cron(args),
+            Some("before") => return self.parse_before(args),
+            Some("in") => return self.parse_in(args),
+            _ => ()
+        }
+        let year = match &arg {
+            Some(year) => {
+                match year.to_string().parse::<i32>() {
+                    Ok(year) if year > 99 => {
+                        arg = args.next();
+                        Some(year)
+                    }
+                    Ok(_) => None,
+                    _ => return usage
+                }
+            }
+            None => return usage
+        };
+        if args.len() < 2 {
+            return usage;
+        }
+        let month = match arg.unwrap().to_string().parse::<u32>() {
+            Ok(month) => month,
+            _ => return usage
+        };
+        let mut day = match args.next().unwrap().to_string().parse::<u32>() {
+            Ok(day) => day,
+            _ => return usage
+        };
+        if nearest && day > 0 && (1..=12).contains(&month) {
+            // any leap year will do for reminders without a year
+            let last = last_day_of_month(year.unwrap_or(2000), month);
+            if day > last {
+                let y = year.map(|year| format!("{} ", year)).unwrap_or_default();
+                eprintln!("adjusted {}{} {} -> {}{} {}", y, month, day, y, month, last);
+                day = last;
+            }
+        }
+
+        let date = if let Some(year) = year {
+            NaiveDate::from_ymd_opt(year, month, day)
+        } else {
+            self.next_recurring_date(self.today, month, day)
+        };
+        if let Some(date) = date {
+            Ok(ReminderItem::new(date, year.is_none(), None, args.join(" ")))
+        } else {
+            usage
+        }
+    }
+    // the expression is quoted in the database, but may be one or five arguments on the command line
+    fn parse_cron<I, T>(&self, mut args: I) -> Result<ReminderItem, String>
+    where I: Iterator<Item=T> + ExactSizeIterator,
+        T: std::fmt::Display,
+    {
+        let usage = Err("usage: remind cron \"minute hour day-of-month month day-of-week\" message".to_string());
+        let mut expr = String::new();
+        while expr.trim_matches('"').split_whitespace().count() < 5
+            || (expr.starts_with('"') && (expr.len() == 1 || !expr.ends_with('"')))
+        {
+            match args.next() {
+                Some(arg) if expr.is_empty() => expr = arg.to_string(),
+                Some(arg) => expr = format!("{} {}", expr, arg),
+                None => return usage
+            }
+        }
+        if args.len() == 0 {
+            return usage;
+        }
+        let schedule = cron::Schedule::parse(expr.trim_matches('"'))?;
+        match schedule.next(self.today) {
+            Some(date) => Ok(ReminderItem::new(date, true, Some(schedule), args.join(" "))),
+            None => Err(format!("cron expression \"{}\" never occurs", schedule))
+        }
+    }
+    // a one-off reminder n days or weeks from today
+    fn parse_in<I, T>(&self, mut args: I) -> Result<ReminderItem, String>
+    where I: Iterator<Item=T> + ExactSizeIterator,
+        T: std::fmt::Display,
+    {
+        let usage = Err("usage: remind in n days message".to_string());
+        let n = match args.next().map(|arg| arg.to_string().parse::<u64>()) {
+            Some(Ok(n)) => n,
+            _ => return usage
+        };
+        let days = match args.next().map(|arg| arg.to_string()).as_deref() {
+            Some("day") | Some("days") => Some(n),
+            Some("week") | Some("weeks") => n.checked_mul(7),
+            _ => return usage
+        };
+        let date = match days.and_then(|days| self.today.checked_add_days(chrono::Days::new(days))) {
+            Some(date) if args.len() > 0 => date,
+            _ => return usage
+        };
+        Ok(ReminderItem::new(date, false, None, args.join(" ")))
+    }
+    // dated when the database has been read and the other reminder can be found
+    fn parse_before<I, T>(&self, mut args: I) -> Result<ReminderItem, String>
+    where I: Iterator<Item=T> + ExactSizeIterator,
+        T: std::fmt::Display,
+    {
+        let usage = Err("usage: before days \"reminder\" message".to_string());
+        let days = match args.next().map(|arg| arg.to_string().parse::<i64>()) {
+            Some(Ok(days)) if days >= 0 => days,
+            _ => return usage
+        };
+        let mut referent = match args.next() {
+            Some(arg) if arg.to_string().starts_with('"') => arg.to_string(),
+            _ => return usage
+        };
+        while referent.len() < 2 || !referent.ends_with('"') {
+            match args.next() {
+                Some(arg) => referent = format!("{} {}", referent, arg),
+                None => return usage
+            }
+        }
+        if args.len() == 0 {
+            return usage;
+        }
+        let item = ReminderItem::new(self.today, false, None, args.join(" "));
+        Ok(ReminderItem{ before: Some((days, referent.trim_matches('"').to_string())), ..item })
+    }
+    // the occurrence closest to anchor, before or after it, the later if they're as close
+    fn nearest_recurring_date(&self, anchor: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+        let next = self.next_recurring_date(anchor, month, day)?;
+        // Feb 29 may be as many as 8 years back
+        let last = (0..=8)
+            .filter_map(|years| NaiveDate::from_ymd_opt(anchor.year() - years, month, day))
+            .find(|&date| date < anchor);
+        match last {
+            Some(last) if anchor - last < next - anchor => Some(last),
+            _ => Some(next)
+        }
+    }
+    // first date on or after from that falls on month and day
+    fn next_recurring_date(&self, from: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+        let mut year = from.year();
+        if month == 2 && day == 29 {
+            loop {
                 if let Some(date) = NaiveDate::from_ymd_opt(year, 2, 29) {
-                    if date >= self.today {
+                    if date >= from {
                         break Some(date);
                     }
                 }
                 year += 1;
             }
         } else if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
-            if date >= self.today {
+            if date >= from {
                 Some(date)
             } else {
                 NaiveDate::from_ymd_opt(year + 1, month, day)
@@ -174,11 +2264,974 @@ impl Reminders {
     }
 }
 
+impl ReminderItem {
+    // text is the message as written in the database, with any note at the end
+    fn new(date: NaiveDate, recurring: bool, cron: Option<cron::Schedule>, text: String) -> Self {
+        let (priority, text) = match text.split_once(' ').unwrap_or((&text, "")) {
+            (word, rest) if parse_priority(word).is_some() => (parse_priority(word), rest.to_string()),
+            _ => (None, text)
+        };
+        let text = match text.strip_prefix("--") {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => rest.trim_start_matches(' ').to_string(),
+            _ => text
+        };
+        let note_start = text.rfind(NOTE_START)
+            .filter(|&start| (start == 0 || text[..start].ends_with(' ')) && text.len() > start + NOTE_START.len())
+            .filter(|_| text.ends_with('"'));
+        let (message, note) = match note_start {
+            Some(start) => (
+                text[..start].trim_end().to_string(),
+                Some(unescape(&text[start + NOTE_START.len()..text.len() - 1]))
+            ),
+            None => (text, None)
+        };
+        let is_url = |word: &str| word.starts_with("@http://") || word.starts_with("@https://");
+        // the last link, which is where Display writes it; any others stay in the message
+        let words = message.split(' ').map(|word| word.to_string()).collect::<Vec<_>>();
+        let (message, url) = match words.iter().rposition(|word| is_url(word)) {
+            Some(i) => (
+                words.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, word)| word).join(" "),
+                Some(words[i][1..].to_string())
+            ),
+            None => (message, None)
+        };
+        ReminderItem{ date, recurring, cron, message, url, note, before: None, priority }
+    }
+    // the message as listed for an occurrence on date, with its priority and year placeholders
+    fn marked(&self, date: NaiveDate) -> String {
+        let message = expand_years(&self.message, date.year());
+        match self.priority {
+            Some(n) => format!("!{} {}", n, message),
+            None => message
+        }
+    }
+    // the reminder with its message normalized, for comparing ignoring case and spacing
+    fn folded(&self) -> Self {
+        ReminderItem{ message: normalized(&self.message), ..self.clone() }
+    }
+    // for sorting, highest priority first and reminders without one last
+    fn priority_rank(&self) -> u32 {
+        self.priority.unwrap_or(u32::MAX)
+    }
+    // order of reminders occurring on the same date; sorting is stable, so
+    // with ties in order they stay as they are
+    fn cmp_same_day(&self, other: &Self, ties: SortTies) -> std::cmp::Ordering {
+        let time = |item: &Self| item.cron.as_ref().and_then(|schedule| schedule.time());
+        let by_time = time(self).cmp(&time(other));
+        let by_priority = self.priority_rank().cmp(&other.priority_rank());
+        let by_message = || self.message.to_lowercase().cmp(&other.message.to_lowercase())
+            .then_with(|| self.to_string().cmp(&other.to_string()));
+        match ties {
+            SortTies::Message => by_time.then(by_priority).then_with(by_message),
+            SortTies::Order => by_time,
+            SortTies::Priority => by_priority.then(by_time).then_with(by_message)
+        }
+    }
+    fn tags(&self) -> Vec<&str> {
+        tag_spans(&self.message).into_iter().map(|(start, end)| &self.message[start..end]).collect()
+    }
+    // how the date of an occurrence is listed
+    fn when(&self, date: NaiveDate) -> String {
+        match self.cron.as_ref().and_then(|schedule| schedule.time()) {
+            Some(time) => format!("{} {} {}", date.month(), date.day(), time),
+            None if self.recurring => format!("{} {}", date.month(), date.day()),
+            None => format!("{} {} {}", date.year(), date.month(), date.day())
+        }
+    }
+}
+
+impl Ord for ReminderItem {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.date.cmp(&other.date).then_with(|| self.cmp_same_day(other, SortTies::Message))
+    }
+}
+
+impl PartialOrd for ReminderItem {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl std::fmt::Display for ReminderItem {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if !self.recurring {
-            write!(f, "{} ", self.date.year())?;
+        // a message that itself begins with -- or a priority needs a --
+        let first = self.message.split(' ').next().unwrap_or_default();
+        let mut message = if first == "--" || parse_priority(first).is_some() {
+            format!("-- {}", self.message)
+        } else {
+            self.message.clone()
+        };
+        if let Some(n) = self.priority {
+            message = format!("!{} {}", n, message);
+        }
+        match (&self.cron, &self.before) {
+            (Some(schedule), _) => write!(f, "cron \"{}\" {}", schedule, message)?,
+            (None, Some((days, referent))) => write!(f, "before {} \"{}\" {}", days, referent, message)?,
+            (None, None) => write!(f, "{} {}", self.when(self.date), message)?
+        }
+        if let Some(url) = &self.url {
+            write!(f, " @{}", url)?;
+        }
+        if let Some(note) = &self.note {
+            let escaped = note.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+            write!(f, " {}{}\"", NOTE_START, escaped)?;
         }
-        write!(f, "{} {} {}", self.date.month(), self.date.day(), self.message)
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+// rewrites a leading two-digit year in "yy month day ..." to four digits, if that makes a date;
+// a line that also reads as "month day message" is taken to be that, and left alone
+fn expand_two_digit_year(fields: &mut [String], pivot: i32) -> bool {
+    if fields.len() < 3 || !fields[..3].iter().all(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit())) {
+        return false;
+    }
+    let numbers = fields[..3].iter().map(|f| f.parse::<u32>().ok()).collect::<Option<Vec<_>>>();
+    match numbers.as_deref() {
+        Some(&[year, month, _]) if NaiveDate::from_ymd_opt(2000, year, month).is_some() => false,
+        Some(&[year, month, day]) if year <= 99 => {
+            let year = year as i32 + if year as i32 >= pivot { 1900 } else { 2000 };
+            if NaiveDate::from_ymd_opt(year, month, day).is_none() {
+                return false;
+            }
+            fields[0] = year.to_string();
+            true
+        }
+        _ => false
+    }
+}
+
+// undoes the \\, \", \n and \r escapes of a quoted note
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                c => unescaped.extend(c)
+            },
+            c => unescaped.push(c)
+        }
+    }
+    unescaped
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+// byte ranges of the tag names (without the leading #) in a message
+fn tag_spans(message: &str) -> Vec<(usize, usize)> {
+    let mut spans = vec!();
+    let mut prev = ' ';
+    for (i, c) in message.char_indices() {
+        if c == '#' && prev.is_whitespace() {
+            let start = i + 1;
+            let end = message[start..].find(|c: char| !is_tag_char(c)).map_or(message.len(), |n| start + n);
+            if end > start {
+                spans.push((start, end));
+            }
+        }
+        prev = c;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    // the reminders in data as loaded on today, without touching the file system
+    fn reminders_with(today: NaiveDate, data: &str, options: &Options) -> Reminders {
+        let mut r = Reminders::empty(std::path::PathBuf::from("reminders"), today, options);
+        r.load(data, options, false).unwrap();
+        r.resolve();
+        r
+    }
+
+    fn reminders(today: NaiveDate, data: &str) -> Reminders {
+        reminders_with(today, data, &Options::default())
+    }
+
+    #[test]
+    fn persistable_lines_follow_the_purge_policy() {
+        let data = "2025 6 1 past\n2025 6 10 today\n2025 9 1 later\n4 2 yearly\n";
+        let r = reminders(date(2025, 6, 10), data);
+        let lines = "2025 6 10 today\n2025 9 1 later\n4 2 yearly\n";
+        assert_eq!(r.persistable_lines(), lines);
+        assert_eq!(r.purged.iter().map(|item| item.to_string()).collect::<Vec<_>>(), vec!("2025 6 1 past"));
+        // a listing window, however short or long, doesn't change what's kept
+        let options = Options { window: Some((date(2025, 6, 10), 1)), ..Options::default() };
+        assert_eq!(reminders_with(date(2025, 6, 10), data, &options).persistable_lines(), lines);
+        let options = Options { window: Some((date(2020, 1, 1), 3000)), ..Options::default() };
+        assert_eq!(reminders_with(date(2025, 6, 10), data, &options).persistable_lines(), lines);
+    }
+
+    #[test]
+    fn persistable_lines_have_the_header_for_cron() {
+        let r = reminders(date(2025, 6, 10), "cron \"0 9 1 * *\" rent\n");
+        assert_eq!(r.persistable_lines(), "#remind-format 2\ncron \"0 9 1 * *\" rent\n");
+    }
+
+    #[test]
+    fn rename_tag_renames_whole_tags_only() {
+        let mut r = reminders(date(2025, 6, 10), "2025 6 12 standup #work\n2025 6 13 #work review\n2025 6 14 #workshop on rust\n");
+        assert_eq!(r.rename_tag("#work", "job"), Ok(2));
+        let messages = r.reminder_items.iter().map(|item| item.message.as_str()).collect::<Vec<_>>();
+        assert_eq!(messages, vec!("standup #job", "#job review", "#workshop on rust"));
+        assert_eq!(r.history, vec!(
+            "edit 2025 6 12 standup #work -> 2025 6 12 standup #job",
+            "edit 2025 6 13 #work review -> 2025 6 13 #job review"
+        ));
+        assert!(r.rename_tag("work", "not a tag").is_err());
+    }
+
+    #[test]
+    fn align_lines_up_messages_after_dates_of_different_widths() {
+        let r = reminders(date(2025, 6, 10), "2025 6 12 dentist\n6 14 Anne birthday\n");
+        let options = Options { align: true, no_summary: true, ..Options::default() };
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 12 dentist\n6 14      Anne birthday\n");
+        let options = Options { no_summary: true, ..Options::default() };
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 12 dentist\n6 14 Anne birthday\n");
+    }
+
+    // where an ephemeral database named name is kept
+    fn ephemeral_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("remind-{}{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn ephemeral_database_is_in_the_temp_directory_and_deleted_on_close() {
+        let options = Options { ephemeral: true, history: true, ..Options::default() };
+        let mut r = Reminders::new(".reminders-ephemeral", &options, false).unwrap();
+        assert_eq!(r.path, ephemeral_path(".reminders-ephemeral"));
+        assert!(!r.log_history);
+        let today = r.today;
+        r.add_new(ReminderItem::new(today, false, None, "lunch with Pat".to_string()));
+        assert!(r.listing(&options).unwrap().contains("lunch with Pat"));
+        let path = r.path.clone();
+        r.close().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn same_day_order_is_the_same_whatever_the_order_read() {
+        let lines = ["2025 6 12 b", "2025 6 12 B", "2025 6 12 a @https://example.com", "2025 6 12 a", "2025 6 11 c"];
+        let sorted = "2025 6 11 c\n2025 6 12 a\n2025 6 12 a @https://example.com\n2025 6 12 B\n2025 6 12 b\n";
+        for order in lines.iter().permutations(lines.len()) {
+            let data = order.iter().map(|line| format!("{}\n", line)).join("");
+            let r = reminders(date(2025, 6, 10), &data);
+            assert_eq!(r.persistable_lines(), sorted);
+            let mut items = r.reminder_items.clone();
+            items.reverse();
+            items.sort();
+            assert_eq!(items, r.reminder_items);
+        }
+    }
+
+    #[test]
+    fn newer_format_database_is_listed_but_never_written() {
+        let path = ephemeral_path(".reminders-newer-format");
+        let data = "#remind-format 99\nsomething only version 99 reads\n2020 1 1 long past\n";
+        std::fs::write(&path, data).unwrap();
+        let options = Options { ephemeral: true, ..Options::default() };
+        let r = Reminders::new(".reminders-newer-format", &options, false).unwrap();
+        assert_eq!(r.format, 99);
+        assert!(r.reminder_items.is_empty());
+        r.close().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), data);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn format_header_is_read() {
+        let r = reminders(date(2025, 6, 10), "#remind-format 2\n2025 6 12 dentist\n");
+        assert_eq!(r.format, 2);
+        assert_eq!(r.persistable_lines(), "2025 6 12 dentist\n");
+    }
+
+    #[test]
+    fn output_file_gets_the_output_in_place_of_what_it_held() {
+        let path = ephemeral_path(".output");
+        std::fs::write(&path, "older and longer output\n").unwrap();
+        write_output("2025 6 12 dentist\n", path.to_str()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2025 6 12 dentist\n");
+        std::fs::remove_file(&path).unwrap();
+        let missing = path.join("no such directory");
+        assert!(write_output("", missing.to_str()).unwrap_err().starts_with("could not write output to"));
+    }
+
+    #[test]
+    fn badge_counts_the_reminders_due_today() {
+        let r = reminders(date(2025, 6, 10), "2025 6 10 dentist\n6 10 Anne birthday\n2025 6 11 tomorrow\n");
+        assert_eq!(r.badge("[{n}]"), "[2]");
+        assert_eq!(r.badge("{n} due"), "2 due");
+        let r = reminders(date(2025, 6, 10), "2025 6 11 tomorrow\n");
+        assert_eq!(r.badge("[{n}]"), "");
+    }
+
+    #[test]
+    fn onboarding_is_shown_only_the_first_time() {
+        let dir = ephemeral_path(".onboarding");
+        let marker = dir.join("remind").join("onboarded");
+        assert!(onboard(Some(marker.clone())));
+        assert!(marker.exists());
+        assert!(!onboard(Some(marker)));
+        assert!(!onboard(None));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn new_only_shows_each_occurrence_once_a_day() {
+        let dir = ephemeral_path(".seen");
+        let state = dir.join("remind").join("seen");
+        let messages = |shown: Vec<Occurrence>| shown.iter().map(|(_, item)| item.message.clone()).collect::<Vec<_>>();
+        let r = reminders(date(2025, 6, 10), "2025 6 10 call Bob\n2025 6 12 dentist\n");
+        assert_eq!(messages(r.unseen(r.occurrences(r.today, 7), Some(state.clone())).unwrap()), ["call Bob", "dentist"]);
+        assert!(r.unseen(r.occurrences(r.today, 7), Some(state.clone())).unwrap().is_empty());
+        let r = reminders(date(2025, 6, 10), "2025 6 10 call Bob\n2025 6 11 lunch with Pat\n");
+        assert_eq!(messages(r.unseen(r.occurrences(r.today, 7), Some(state.clone())).unwrap()), ["lunch with Pat"]);
+        // the dentist is no longer in the window, so it's dropped from the state
+        assert_eq!(std::fs::read_to_string(&state).unwrap().lines().count(), 2);
+        assert!(r.unseen(vec!(), None).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn link_is_taken_out_of_the_message_and_written_back() {
+        let item = ReminderItem::new(date(2025, 6, 12), false, None, "standup @https://meet.example.com/abc daily".to_string());
+        assert_eq!(item.message, "standup daily");
+        assert_eq!(item.url.as_deref(), Some("https://meet.example.com/abc"));
+        assert_eq!(item.to_string(), "2025 6 12 standup daily @https://meet.example.com/abc");
+        let item = ReminderItem::new(date(2025, 6, 12), false, None, "email bob@https.example.com".to_string());
+        assert_eq!(item.url, None);
+    }
+
+    #[test]
+    fn last_of_two_links_is_the_link_and_stays_last() {
+        let line = "2030 1 5 a @http://x b @http://y";
+        let r = reminders(date(2025, 6, 10), &format!("{}\n", line));
+        let item = &r.reminder_items[0];
+        assert_eq!((item.message.as_str(), item.url.as_deref()), ("a @http://x b", Some("http://y")));
+        assert_eq!(item.to_string(), line);
+        let options = Options::default();
+        let mut r = Reminders::empty(std::path::PathBuf::from("reminders"), date(2025, 6, 10), &options);
+        r.load(&format!("{}\n", line), &options, true).unwrap();
+        assert_eq!(r.repairs.reformatted, 0);
+    }
+
+    #[test]
+    fn opener_opens_the_link() {
+        let command = opener("https://example.com");
+        let args = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect::<Vec<_>>();
+        if cfg!(target_os = "macos") {
+            assert_eq!(command.get_program(), "open");
+            assert_eq!(args, vec!("https://example.com"));
+        } else if cfg!(windows) {
+            assert_eq!(command.get_program(), "cmd");
+            assert_eq!(args, vec!("/c", "start", "", "https://example.com"));
+        } else {
+            assert_eq!(command.get_program(), "xdg-open");
+            assert_eq!(args, vec!("https://example.com"));
+        }
+    }
+
+    #[test]
+    fn database_that_is_not_utf8_is_an_error_and_left_alone() {
+        let path = ephemeral_path(".reminders-not-utf8");
+        let data = b"2025 6 12 caf\xe9\n".to_vec();
+        std::fs::write(&path, &data).unwrap();
+        let options = Options { ephemeral: true, ..Options::default() };
+        let e = Reminders::new(".reminders-not-utf8", &options, false).unwrap_err();
+        assert_eq!(e, format!("{} is not valid UTF-8 (at byte 13); not changing it", path.display()));
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn group_by_tag_lists_a_reminder_under_each_of_its_tags() {
+        let r = reminders(date(2025, 6, 10), "2025 6 11 call plumber #home\n2025 6 12 #work #home fix the boiler\n2025 6 13 dentist\n");
+        let options = Options { group_by_tag: true, no_summary: true, ..Options::default() };
+        assert_eq!(r.listing(&options).unwrap(), "#home\n2025 6 11 call plumber #home\n2025 6 12 #work #home fix the boiler\n\
+            #work\n2025 6 12 #work #home fix the boiler\n(untagged)\n2025 6 13 dentist\n");
+    }
+
+    #[test]
+    fn repair_tidies_a_messy_database_once() {
+        let repaired = |data: &str| {
+            let options = Options::default();
+            let mut r = Reminders::empty(std::path::PathBuf::from("reminders"), date(2025, 6, 10), &options);
+            r.load(data, &options, true).unwrap();
+            r
+        };
+        let r = repaired("  2025 6 12   dentist \n\n2025 6 12 dentist\n2020 1 1 old\n4  2 Anne birthday\n\n");
+        assert_eq!(r.persistable_lines(), "2025 6 12 dentist\n4 2 Anne birthday\n");
+        assert_eq!((r.repairs.duplicates, r.repairs.reformatted, r.repairs.blank, r.purged.len()), (1, 2, 2, 1));
+        let r = repaired(&r.persistable_lines());
+        assert_eq!((r.repairs.duplicates, r.repairs.reformatted, r.repairs.blank, r.purged.len()), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn window_lists_a_birthday_in_a_future_week() {
+        let r = reminders(date(2025, 6, 10), "12 3 Anne birthday\n2025 6 11 dentist\n");
+        let options = Options { window: Some(parse_window("2025-12-01:7").unwrap()), ..Options::default() };
+        assert_eq!(r.listing(&options).unwrap(), "12 3 Anne birthday\n1 reminder in the 7 days from 2025-12-01\n");
+        assert!(parse_window("2025-12-01").is_err());
+        assert!(parse_window("2025-12-01:0").is_err());
+    }
+
+    #[test]
+    fn offsets_are_days_or_weeks() {
+        assert_eq!(parse_offset("+7d"), Ok(7));
+        assert_eq!(parse_offset("-2w"), Ok(-14));
+        assert_eq!(parse_offset("3d"), Ok(3));
+        for bad in ["", "d", "+7", "7x", "+7é", "+1000000d"] {
+            assert!(parse_offset(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn shift_moves_dates_and_recomputes_yearly_occurrences() {
+        let r = reminders(date(2025, 6, 10), "2025 6 12 dentist\n12 28 party\n6 5 picnic\n\
+            cron \"0 9 1 * *\" rent\nbefore 2 \"dentist\" floss\n");
+        let lines = r.shifted(7).iter().map(|item| format!("{} on {}", item, item.date)).collect::<Vec<_>>();
+        assert_eq!(lines, vec!(
+            "before 2 \"dentist\" floss on 2025-06-10",
+            "2025 6 19 dentist on 2025-06-19",
+            "cron \"0 9 1 * *\" rent on 2025-07-01",
+            "1 4 party on 2026-01-04",
+            "6 12 picnic on 2025-06-12"
+        ));
+    }
+
+    #[test]
+    fn watch_announces_each_reminder_once_as_it_comes_due() {
+        let mut announced = std::collections::HashSet::new();
+        let data = "2025 6 10 dentist\n2025 6 11 lunch with Pat\n";
+        assert_eq!(reminders(date(2025, 6, 10), data).newly_due(&mut announced), vec!("2025 6 10 dentist"));
+        assert!(reminders(date(2025, 6, 10), data).newly_due(&mut announced).is_empty());
+        let data = "2025 6 10 dentist\n6 10 Anne birthday\n2025 6 11 lunch with Pat\n";
+        assert_eq!(reminders(date(2025, 6, 10), data).newly_due(&mut announced), vec!("6 10 Anne birthday"));
+        // at midnight the day before is forgotten
+        assert_eq!(reminders(date(2025, 6, 11), data).newly_due(&mut announced), vec!("2025 6 11 lunch with Pat"));
+        assert_eq!(announced.len(), 1);
+    }
+
+    #[test]
+    fn explain_tells_when_a_reminder_is_just_outside_the_window() {
+        let r = reminders(date(2025, 6, 10), "2025 6 17 dentist\n2025 6 16 lunch with Pat\n2025 6 1 old\n");
+        let options = Options::default();
+        assert_eq!(r.explain("dentist", &options).unwrap(),
+            "2025 6 17 dentist\nnext occurs 2025-06-17, in 7 days\nnot shown: outside the window 2025-06-10 to 2025-06-16\n");
+        assert_eq!(r.explain("lunch", &options).unwrap(),
+            "2025 6 16 lunch with Pat\nnext occurs 2025-06-16, in 6 days\nshown: 1 time in the window 2025-06-10 to 2025-06-16\n");
+        assert_eq!(r.explain("old", &options).unwrap(), "2025 6 1 old\nnot shown: 2025-06-01 is past, so the reminder is deleted\n");
+        assert!(r.explain("nothing", &options).is_err());
+    }
+
+    #[test]
+    fn before_reminders_are_dated_from_their_other_reminder() {
+        let options = Options::default();
+        let mut r = Reminders::empty(std::path::PathBuf::from("reminders"), date(2025, 6, 10), &options);
+        r.load("6 20 Mom birthday\n6 12 Dad birthday\nbefore 3 \"Mom birthday\" order flowers\n\
+            before 3 \"Dad\" call Dad\nbefore 2 \"nobody\" wave\n", &options, false).unwrap();
+        assert_eq!(r.resolve(), vec!("not listing \"wave\": no reminder matches \"nobody\""));
+        let dates = r.reminder_items.iter().filter(|item| item.before.is_some()).map(|item| (item.message.as_str(), item.date)).collect::<Vec<_>>();
+        // Dad's birthday is too soon for a reminder 3 days before, so it's next year's
+        assert_eq!(dates, vec!(("order flowers", date(2025, 6, 17)), ("call Dad", date(2026, 6, 9))));
+        assert_eq!(r.unresolved.len(), 1);
+        assert!(r.persistable_lines().ends_with("before 2 \"nobody\" wave\n"));
+        r.add_before("Mom", 1, "bake a cake".to_string()).unwrap();
+        assert!(r.reminder_items.iter().any(|item| item.message == "bake a cake" && item.date == date(2025, 6, 19)));
+        assert!(r.add_before("birthday", 1, "ambiguous".to_string()).is_err());
+        r.load("2025 6 12 lunch\n", &options, false).unwrap();
+        r.history.clear();
+        assert_eq!(r.add_before("lunch", 3, "prep".to_string()), Err("\"lunch\" is only 2 days away".to_string()));
+        assert!(r.history.is_empty() && r.purged.is_empty());
+    }
+
+    #[test]
+    fn review_applies_each_action_once_it_ends() {
+        let data = "2025 6 10 dentist\n2025 6 10 pay rent\n6 10 Anne birthday\n2025 6 10 stretch\n2025 6 10 water plants\n2025 6 11 lunch\n";
+        let mut r = reminders(date(2025, 6, 10), data);
+        // the due reminders in order: Anne birthday, dentist, pay rent, stretch, water plants
+        let input = "S\nk\nx\ne\npay the rent\nd\ns\nq\n";
+        assert_eq!(r.review(input.as_bytes(), std::io::sink()).unwrap(), "1 kept, 1 done, 2 snoozed, 1 edited\n");
+        assert_eq!(r.persistable_lines(), "6 10 Anne birthday\n2025 6 10 dentist\n2025 6 10 water plants\n\
+            2025 6 11 lunch\n2025 6 11 stretch\n2025 6 17 Anne birthday\n");
+        assert_eq!(r.history, vec!(
+            "add 2025 6 17 Anne birthday",
+            "edit 2025 6 10 pay rent -> 2025 6 10 pay the rent",
+            "delete 2025 6 10 pay the rent",
+            "move 2025 6 10 stretch -> 2025 6 11 stretch"
+        ));
+        // input ending quits as q does
+        let mut r = reminders(date(2025, 6, 10), data);
+        assert_eq!(r.review("k\n".as_bytes(), std::io::sink()).unwrap(), "1 kept, 0 done, 0 snoozed, 0 edited\n");
+        assert!(r.history.is_empty());
+        assert_eq!(reminders(date(2025, 6, 12), data).review("".as_bytes(), std::io::sink()).unwrap(), "nothing due today\n");
+    }
+
+    #[test]
+    fn review_edits_are_read_as_messages_are_when_added() {
+        let mut r = reminders(date(2025, 6, 10), "2025 6 10 !2 call Bob note=\"at home\"\n6 10 Anne birthday\n");
+        r.review("e\n!1 call Pat @https://meet.example.com\nk\ne\nAnne's birthday\nk\n".as_bytes(), std::io::sink()).unwrap();
+        assert_eq!(r.persistable_lines(), "2025 6 10 !1 call Pat @https://meet.example.com note=\"at home\"\n6 10 Anne's birthday\n");
+        let item = &r.reminder_items[0];
+        assert_eq!((item.message.as_str(), item.priority, item.recurring), ("call Pat", Some(1), false));
+    }
+
+    #[test]
+    fn snoozed_before_reminders_and_their_other_reminders_are_still_dated() {
+        let data = "6 20 Mom birthday\nbefore 10 \"Mom birthday\" order flowers\n2025 6 13 party\nbefore 3 \"party\" buy a gift\n";
+        let mut r = reminders(date(2025, 6, 10), data);
+        assert_eq!(r.review("s\ns\n".as_bytes(), std::io::sink()).unwrap(), "0 kept, 0 done, 2 snoozed, 0 edited\n");
+        let lines = "#remind-format 2\nbefore 10 \"Mom birthday\" order flowers\n2025 6 11 buy a gift\n\
+            2025 6 11 order flowers\n2025 6 13 party\n6 20 Mom birthday\n";
+        assert_eq!(r.persistable_lines(), lines);
+        // the next day the before reminder is dated from next year's birthday
+        assert_eq!(reminders(date(2025, 6, 11), lines).persistable_lines(), "#remind-format 2\n2025 6 11 buy a gift\n\
+            2025 6 11 order flowers\n2025 6 13 party\n6 20 Mom birthday\nbefore 10 \"Mom birthday\" order flowers\n");
+        // a snoozed copy of the other reminder doesn't make it ambiguous
+        let mut r = reminders(date(2025, 6, 20), "6 20 Mom birthday\nbefore 3 \"Mom birthday\" order flowers\n");
+        r.review("s\n".as_bytes(), std::io::sink()).unwrap();
+        let mut snoozed = Reminders::empty(std::path::PathBuf::from("reminders"), date(2025, 6, 20), &Options::default());
+        snoozed.load(&r.persistable_lines(), &Options::default(), false).unwrap();
+        assert!(snoozed.resolve().is_empty());
+        assert!(snoozed.reminder_items.iter().any(|item| item.message == "order flowers" && item.date == date(2026, 6, 17)));
+    }
+
+    // the reminders in data, as a copy of the database read for sync-merge
+    fn copy(today: NaiveDate, data: &str) -> Vec<ReminderItem> {
+        reminders(today, data).reminder_items
+    }
+
+    #[test]
+    fn sync_merge_takes_adds_and_deletes_from_both_sides() {
+        let base = copy(date(2025, 6, 10), "2025 6 12 dentist\n2025 6 13 lunch with Pat\n");
+        let mut r = reminders(date(2025, 6, 10), "2025 6 12 dentist\n2025 6 13 lunch with Pat\n2025 6 14 haircut\n");
+        let theirs = copy(date(2025, 6, 10), "2025 6 12 dentist\n2025 6 15 picnic\n");
+        assert_eq!(r.sync_merge(&base, &theirs, false), "1 added, 0 changed, 1 deleted, 0 conflicts\n");
+        assert_eq!(r.persistable_lines(), "2025 6 12 dentist\n2025 6 14 haircut\n2025 6 15 picnic\n");
+        assert_eq!(r.history, vec!("add 2025 6 15 picnic", "delete 2025 6 13 lunch with Pat"));
+    }
+
+    #[test]
+    fn sync_merge_reports_a_reminder_changed_on_both_sides() {
+        let base = copy(date(2025, 6, 10), "2025 6 12 dentist\n");
+        let mut r = reminders(date(2025, 6, 10), "2025 6 12 dentist at 2\n");
+        let theirs = copy(date(2025, 6, 10), "2025 6 12 dentist at 3, conflict: ask\n");
+        assert_eq!(r.sync_merge(&base, &theirs, false), "conflict: 2025 6 12 dentist\n  ours: 2025 6 12 dentist at 2\n\
+            \x20 theirs: 2025 6 12 dentist at 3, conflict: ask\n0 added, 0 changed, 0 deleted, 1 conflict\n");
+        assert_eq!(r.persistable_lines(), "2025 6 12 dentist at 2\n");
+        assert!(r.history.is_empty());
+    }
+
+    #[test]
+    fn anchor_nearest_lists_a_recent_birthday_rather_than_next_years() {
+        let data = "6 8 Anne birthday\n";
+        let options = |anchor_nearest| Options { anchor_nearest, include_past: true, past_days: 7, no_summary: true, ..Options::default() };
+        let listing = |options: &Options| reminders_with(date(2025, 6, 10), data, options).listing(options).unwrap();
+        assert_eq!(listing(&options(true)), "6 8 Anne birthday\n");
+        assert_eq!(listing(&options(false)), "");
+        // without --include-past the nearest is before the window, so the next is listed
+        let options = |anchor_nearest| Options { anchor_nearest, window: Some((date(2025, 6, 10), 365)), no_summary: true, ..Options::default() };
+        let r = reminders_with(date(2025, 6, 10), data, &options(true));
+        assert_eq!(r.occurrences(date(2025, 6, 10), 365), vec!((date(2026, 6, 8), &r.reminder_items[0])));
+        assert_eq!(listing(&options(true)), "6 8 Anne birthday\n");
+        assert_eq!(listing(&options(false)), "6 8 Anne birthday\n");
+    }
+
+    #[test]
+    fn two_digit_years_are_expanded_about_the_pivot() {
+        let expanded = |line: &str, pivot| {
+            let mut fields = line.split(' ').map(|f| f.to_string()).collect::<Vec<_>>();
+            if expand_two_digit_year(&mut fields, pivot) { Some(fields.join(" ")) } else { None }
+        };
+        assert_eq!(expanded("69 12 31 party", 70).as_deref(), Some("2069 12 31 party"));
+        assert_eq!(expanded("70 12 31 party", 70).as_deref(), Some("1970 12 31 party"));
+        assert_eq!(expanded("69 12 31 party", 60).as_deref(), Some("1969 12 31 party"));
+        assert_eq!(expanded("24 2 30 party", 70), None);
+        assert_eq!(expanded("2024 12 31 party", 70), None);
+        // a yearly reminder whose message begins with a number isn't read as dated
+        assert_eq!(expanded("4 2 10 years married", 70), None);
+        let options = Options { two_digit_years: true, two_digit_pivot: 70, ..Options::default() };
+        let r = reminders_with(date(2025, 6, 10), "4 2 10 years married\n30 6 12 reunion\n", &options);
+        assert_eq!(r.repairs.years, vec!("30 6 12 reunion -> 2030 6 12 reunion"));
+        assert_eq!(r.persistable_lines(), "4 2 10 years married\n2030 6 12 reunion\n");
+        assert!(r.purged.is_empty());
+    }
+
+    #[test]
+    fn priorities_are_read_written_back_and_sorted_first() {
+        let options = Options { priority: Some(3), ..Options::default() };
+        let mut r = reminders_with(date(2025, 6, 10), "2025 6 12 dentist\n", &options);
+        for line in ["2025 6 12 !1 renew passport", "2025 6 11 call Pat", "2025 6 12 -- !2 is a valid word"] {
+            r.add_new(r.parse_item(args(line), false).unwrap());
+        }
+        let lines = "2025 6 11 !3 call Pat\n2025 6 12 !1 renew passport\n2025 6 12 !3 -- !2 is a valid word\n2025 6 12 dentist\n";
+        assert_eq!(r.persistable_lines(), lines);
+        assert_eq!(reminders(date(2025, 6, 10), lines).persistable_lines(), lines);
+        let item = &r.reminder_items[2];
+        assert_eq!((item.priority, item.message.as_str()), (Some(3), "!2 is a valid word"));
+        let options = Options { sort_by_priority: true, no_summary: true, ..Options::default() };
+        let r = reminders(date(2025, 6, 10), "2025 6 11 !2 call Pat\n2025 6 11 lunch\n2025 6 13 !1 renew passport\n2025 6 12 !2 dentist\n");
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 13 !1 renew passport\n2025 6 11 !2 call Pat\n\
+            2025 6 12 !2 dentist\n2025 6 11 lunch\n");
+    }
+
+    #[test]
+    fn today_lasts_until_the_rollover_hour() {
+        let at = |hour| date(2025, 6, 10).and_hms_opt(hour, 0, 0).unwrap();
+        assert_eq!(today_at(at(1), 3), date(2025, 6, 9));
+        assert_eq!(today_at(at(3), 3), date(2025, 6, 10));
+        assert_eq!(today_at(at(1), 0), date(2025, 6, 10));
+        assert_eq!(today_at(at(23), 3), date(2025, 6, 10));
+    }
+
+    #[test]
+    fn daily_adds_a_reminder_for_each_day_in_the_range() {
+        let mut r = reminders(date(2025, 6, 10), "");
+        r.add_daily(Some(date(2025, 6, 12)), Some(date(2025, 6, 21)), "water plants".to_string()).unwrap();
+        assert_eq!(r.reminder_items.len(), 10);
+        assert_eq!((r.reminder_items[0].date, r.reminder_items[9].date), (date(2025, 6, 12), date(2025, 6, 21)));
+        assert!(r.reminder_items.iter().all(|item| item.message == "water plants" && !item.recurring));
+        assert_eq!(r.history.len(), 10);
+        // a range that has begun adds only the days still to come
+        let mut r = reminders(date(2025, 6, 10), "");
+        r.add_daily(Some(date(2025, 6, 8)), Some(date(2025, 6, 11)), "stretch".to_string()).unwrap();
+        assert_eq!(r.reminder_items.len(), 2);
+        assert_eq!(r.history, vec!("add 2025 6 10 stretch", "add 2025 6 11 stretch"));
+        assert_eq!(r.add_daily(Some(date(2025, 6, 1)), Some(date(2025, 6, 9)), "stretch".to_string()),
+            Err("--daily-to 2025-06-09 is past".to_string()));
+        assert!(r.add_daily(Some(date(2025, 6, 12)), Some(date(2025, 6, 11)), "stretch".to_string()).is_err());
+        assert!(r.add_daily(Some(date(2025, 6, 12)), None, "stretch".to_string()).is_err());
+        assert!(r.add_daily(Some(date(2025, 6, 12)), Some(date(2026, 6, 13)), "stretch".to_string()).is_err());
+    }
+
+    #[test]
+    fn legacy_listing_is_the_example_in_the_usage() {
+        let data = "4 2 Anne birthday\n10 13 Kate birthday\n7 4 Independence Day\n2019 7 2 lunch with Pat\n2019 5 13 dentist 2:00pm\n";
+        let options = Options { legacy: true, ..Options::default() };
+        let r = reminders_with(date(2019, 6, 30), data, &options);
+        assert_eq!(r.legacy_listing(), "7 4 Independence Day\n2019 7 2 lunch with Pat\n");
+        assert_eq!(r.persistable_lines(), "4 2 Anne birthday\n10 13 Kate birthday\n7 4 Independence Day\n2019 7 2 lunch with Pat\n");
+    }
+
+    #[test]
+    fn doctor_reports_recurring_reminders_with_the_same_message_on_one_date() {
+        // 2025-09-01, 2025-12-01 and 2026-06-01 are Mondays; dated reminders aren't counted
+        let r = reminders(date(2025, 6, 10), "cron \"0 9 * * 1\" standup\ncron \"0 9 1 * *\" Standup\n2025 6 16 standup\n");
+        assert_eq!(r.doctor(), "2025-09-01: 2 reminders with the same message\n    cron \"0 9 * * 1\" standup\n    cron \"0 9 1 * *\" Standup\n\
+            2025-12-01: 2 reminders with the same message\n    cron \"0 9 * * 1\" standup\n    cron \"0 9 1 * *\" Standup\n\
+            2026-06-01: 2 reminders with the same message\n    cron \"0 9 * * 1\" standup\n    cron \"0 9 1 * *\" Standup\n\
+            3 collisions found\n");
+        let r = reminders(date(2025, 6, 10), "cron \"0 9 * * 1\" standup\ncron \"0 9 * * 2\" standup\n");
+        assert_eq!(r.doctor(), "0 collisions found\n");
+    }
+
+    #[test]
+    fn recurrence_label_follows_recurring_reminders_only() {
+        let data = "2025 6 12 dentist\n6 13 Anne birthday\ncron \"0 9 * * 6\" yoga\nbefore 2 \"dentist\" floss\n";
+        let options = Options { recurrence_label: true, no_summary: true, ..Options::default() };
+        assert_eq!(reminders(date(2025, 6, 10), data).listing(&options).unwrap(), "2025 6 10 floss (2 days before dentist)\n\
+            2025 6 12 dentist\n6 13 Anne birthday (yearly)\n6 14 09:00 yoga (cron 0 9 * * 6)\n");
+        let options = Options { no_summary: true, ..Options::default() };
+        assert!(!reminders(date(2025, 6, 10), data).listing(&options).unwrap().contains('('));
+    }
+
+    #[test]
+    fn wrap_lines_up_continuations_and_breaks_long_words() {
+        let message = "call the plumber about a supercalifragilisticexpialidocious boiler";
+        assert_eq!(wrap(message, 20), vec!("call the plumber", "about a", "supercalifragilistic", "expialidocious", "boiler"));
+        let r = reminders(date(2025, 6, 10), &format!("2025 6 12 {}\n", message));
+        // 20 columns are left for the message after the date
+        let options = Options { wrap: Some(30), no_summary: true, ..Options::default() };
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 12 call the plumber\n          about a\n\
+            \x20         supercalifragilistic\n          expialidocious\n          boiler\n");
+    }
+
+    #[test]
+    fn tag_adds_to_every_match_and_removes_from_one() {
+        let mut r = reminders(date(2025, 6, 10), "2025 6 12 standup\n2025 6 13 standup review\n2025 6 14 #work café #workshop\n");
+        assert_eq!(r.tag("#work", "stand", true), Ok(2));
+        assert_eq!(r.tag("work", "stand", true), Ok(0));
+        assert_eq!(r.tag("work", "review", false), Ok(1));
+        assert_eq!(r.tag("work", "café", false), Ok(1));
+        let messages = r.reminder_items.iter().map(|item| item.message.as_str()).collect::<Vec<_>>();
+        assert_eq!(messages, vec!("standup #work", "standup review", "café #workshop"));
+        assert_eq!(r.history, vec!(
+            "edit 2025 6 12 standup -> 2025 6 12 standup #work",
+            "edit 2025 6 13 standup review -> 2025 6 13 standup review #work",
+            "edit 2025 6 13 standup review #work -> 2025 6 13 standup review",
+            "edit 2025 6 14 #work café #workshop -> 2025 6 14 café #workshop"
+        ));
+        assert!(r.tag("work", "nothing", true).is_err());
+        assert!(r.tag("not a tag", "stand", true).is_err());
+    }
+
+    #[test]
+    fn include_past_lists_and_keeps_reminders_days_ago() {
+        let data = "2025 6 7 dentist\n2025 6 13 lunch with Pat\n2025 6 1 old\n";
+        let options = Options { include_past: true, past_days: 7, ..Options::default() };
+        let r = reminders_with(date(2025, 6, 10), data, &options);
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 7 dentist\n2025 6 13 lunch with Pat\n2 reminders in the 14 days from 2025-06-03\n");
+        assert_eq!(r.persistable_lines(), "2025 6 7 dentist\n2025 6 13 lunch with Pat\n");
+        let options = Options::default();
+        let r = reminders_with(date(2025, 6, 10), data, &options);
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 13 lunch with Pat\n1 reminder in the next 7 days\n");
+        assert_eq!(r.purged.len(), 2);
+    }
+
+    #[test]
+    fn default_command_decides_what_a_bare_remind_shows() {
+        let r = reminders(date(2025, 6, 10), "2025 6 10 dentist\n2025 6 12 lunch with Pat\n2025 9 1 haircut\n");
+        let listing = |default_command| r.default_listing(&mut Options { default_command, ..Options::default() }).unwrap();
+        assert_eq!(listing(DefaultCommand::Week), "2025 6 10 dentist\n2025 6 12 lunch with Pat\n2 reminders in the next 7 days (1 today)\n");
+        assert_eq!(listing(DefaultCommand::Today), "2025 6 10 dentist\n1 reminder today\n");
+        assert_eq!(listing(DefaultCommand::List), "2025 6 10 dentist\n2025 6 12 lunch with Pat\n2025 9 1 haircut\n");
+        assert_eq!(listing(DefaultCommand::Next), "2025 6 10 dentist\n");
+        assert_eq!(DefaultCommand::parse("today"), Ok(DefaultCommand::Today));
+        assert!(DefaultCommand::parse("tomorrow").is_err());
+    }
+
+    #[test]
+    fn import_vcf_adds_a_birthday_for_each_contact() {
+        let vcf = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Anne Ha\r\n thaway\r\nBDAY;VALUE=date:1990-04-02\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nFN:Smith\\, Kate\r\nBDAY:--1013\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nFN:Bob\r\nBDAY:1990\r\nEND:VCARD\r\nBEGIN:VCARD\r\nFN:No Birthday\r\nEND:VCARD\r\n";
+        let mut r = reminders(date(2025, 6, 10), "");
+        assert_eq!(r.import_vcf(vcf), "imported 2 birthdays, 0 already present, 1 unreadable\n");
+        assert_eq!(r.persistable_lines(), "10 13 Smith, Kate birthday\n4 2 Anne Hathaway birthday\n");
+        assert_eq!(r.import_vcf(vcf), "imported 0 birthdays, 2 already present, 1 unreadable\n");
+        assert_eq!(parse_bday("--04-02"), Some((4, 2)));
+        assert_eq!(parse_bday("19900402T000000"), Some((4, 2)));
+        assert_eq!(parse_bday("--0230"), None);
+    }
+
+    #[test]
+    fn next_date_is_the_next_occurrence_for_scripts() {
+        let r = reminders(date(2025, 6, 10), "4 2 Anne birthday\n2025 6 12 dentist\n");
+        assert_eq!(r.next_date("Anne", false), Ok("2026-04-02\n".to_string()));
+        assert_eq!(r.next_date("Anne", true), Ok("20545\n".to_string()));
+        assert_eq!(r.next_date("2", false), Ok("2026-04-02\n".to_string()));
+        assert_eq!(r.next_date("Pat", false), Err("no reminder matches \"Pat\"".to_string()));
+        assert_eq!(reminders(date(2025, 6, 10), "").next_date("Pat", false), Err("there are no reminders".to_string()));
+    }
+
+    #[test]
+    fn preview_purge_shows_only_the_past_reminders() {
+        let r = reminders(date(2025, 6, 10), "2025 6 1 old\n2025 6 12 dentist\n2024 12 31 party\n6 1 Anne birthday\n");
+        assert_eq!(r.preview_purge(), "2025 6 1 old\n2024 12 31 party\n");
+        assert_eq!(r.persistable_lines(), "2025 6 12 dentist\n6 1 Anne birthday\n");
+    }
+
+    #[test]
+    fn case_variants_are_the_same_only_when_case_insensitive() {
+        let data = "10 13 Kate Birthday\n10 13 kate  birthday\n2025 6 12 dentist\n";
+        let repaired = |case_insensitive| {
+            let options = Options { case_insensitive, ..Options::default() };
+            let mut r = Reminders::empty(std::path::PathBuf::from("reminders"), date(2025, 6, 10), &options);
+            r.load(data, &options, true).unwrap();
+            r
+        };
+        assert_eq!(repaired(true).persistable_lines(), "2025 6 12 dentist\n10 13 Kate Birthday\n");
+        assert_eq!(repaired(false).persistable_lines(), "2025 6 12 dentist\n10 13 Kate Birthday\n10 13 kate birthday\n");
+        // theirs only recased a reminder, which isn't a change when case-insensitive
+        let base = copy(date(2025, 6, 10), "10 13 Kate birthday\n");
+        let theirs = copy(date(2025, 6, 10), "10 13 Kate Birthday\n");
+        let mut r = reminders(date(2025, 6, 10), "10 13 Kate birthday\n");
+        assert_eq!(r.sync_merge(&base, &theirs, true), "0 added, 0 changed, 0 deleted, 0 conflicts\n");
+        let mut r = reminders(date(2025, 6, 10), "10 13 Kate birthday\n");
+        assert_eq!(r.sync_merge(&base, &theirs, false), "0 added, 1 changed, 0 deleted, 0 conflicts\n");
+    }
+
+    // the arguments of a command line, split at spaces
+    fn args(line: &str) -> std::vec::IntoIter<&str> {
+        line.split(' ').collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    fn in_adds_a_dated_reminder_that_is_purged_once_past() {
+        let r = reminders(date(2025, 6, 10), "");
+        let item = r.parse_item(args("in 10 days call the garage"), false).unwrap();
+        assert_eq!(item.to_string(), "2025 6 20 call the garage");
+        assert_eq!(r.parse_item(args("in 2 weeks call the garage"), false).unwrap().date, date(2025, 6, 24));
+        assert!(r.parse_item(args("in 10 call the garage"), false).is_err());
+        assert!(r.parse_item(args("in 10 days"), false).is_err());
+        assert!(reminders(date(2025, 6, 20), "2025 6 20 call the garage\n").purged.is_empty());
+        assert_eq!(reminders(date(2025, 6, 21), "2025 6 20 call the garage\n").purged.len(), 1);
+    }
+
+    #[test]
+    fn first_and_last_pick_the_earliest_and_latest_reminders() {
+        let mut r = reminders(date(2025, 6, 10), "2025 9 1 haircut\n2025 6 12 dentist\n4 2 Anne birthday\n");
+        assert_eq!(r.reminder_items[r.select_one("--first").unwrap()].message, "dentist");
+        assert_eq!(r.reminder_items[r.select_one("--last").unwrap()].message, "Anne birthday");
+        assert_eq!(r.tag("work", "--first", true), Ok(1));
+        assert_eq!(r.persistable_lines(), "2025 6 12 dentist #work\n2025 9 1 haircut\n4 2 Anne birthday\n");
+        let r = reminders(date(2025, 6, 10), "");
+        assert_eq!(r.select_one("--first"), Err("there are no reminders".to_string()));
+        assert_eq!(r.select_one("--last"), Err("there are no reminders".to_string()));
+    }
+
+    #[test]
+    fn info_gives_the_version_and_where_the_database_is() {
+        let r = reminders(date(2025, 6, 10), "2025 6 12 dentist\n4 2 Anne birthday\ncron \"0 9 1 * *\" rent\n2025 6 1 old\n");
+        let info = r.info();
+        assert!(info.starts_with(&format!("remind {}\ndatabase reminders (missing)\nformat 1\n", env!("CARGO_PKG_VERSION"))), "{}", info);
+        assert!(info.contains("\ntoday 2025-06-10 ("), "{}", info);
+        assert!(info.ends_with("\n1 yearly, 1 dated, 1 cron, 0 before, 0 unresolved, 1 past\n"), "{}", info);
+    }
+
+    #[test]
+    fn report_has_upcoming_recurring_and_dated_sections() {
+        let r = reminders(date(2025, 6, 10), "2025 6 12 dentist\n2025 9 1 haircut\n10 13 Kate birthday\n6 11 Anne birthday\ncron \"0 9 1 * *\" rent\n");
+        assert_eq!(r.report(&Options::default()), "Upcoming (next 7 days)\n6 11 Anne birthday\n2025 6 12 dentist\n\n\
+            Recurring events\ncron \"0 9 1 * *\" rent\n6 11 Anne birthday\n10 13 Kate birthday\n\n\
+            All dated reminders\n2025 6 12 dentist\n2025 9 1 haircut\n");
+    }
+
+    #[test]
+    fn sort_ties_order_same_day_reminders_as_configured() {
+        let data = "2025 6 12 pay rent\n2025 6 12 !2 call Pat\n2025 6 12 buy milk\n";
+        let lines = |sort_ties| reminders_with(date(2025, 6, 10), data, &Options { sort_ties, ..Options::default() }).persistable_lines();
+        assert_eq!(lines(SortTies::Order), data);
+        assert_eq!(lines(SortTies::Message), "2025 6 12 !2 call Pat\n2025 6 12 buy milk\n2025 6 12 pay rent\n");
+        // priority comes before time too
+        let data = "cron \"0 9 12 6 *\" standup\n2025 6 12 buy milk\ncron \"0 8 12 6 *\" !1 gym\n";
+        let listing = |sort_ties| {
+            let options = Options { sort_ties, no_summary: true, ..Options::default() };
+            reminders_with(date(2025, 6, 10), data, &options).listing(&options).unwrap()
+        };
+        assert_eq!(listing(SortTies::Priority), "6 12 08:00 !1 gym\n2025 6 12 buy milk\n6 12 09:00 standup\n");
+        assert_eq!(listing(SortTies::Message), "2025 6 12 buy milk\n6 12 08:00 !1 gym\n6 12 09:00 standup\n");
+        assert_eq!(listing(SortTies::Order), "2025 6 12 buy milk\n6 12 08:00 !1 gym\n6 12 09:00 standup\n");
+    }
+
+    #[test]
+    fn rotate_archives_past_reminders_by_year() {
+        let options = Options::default();
+        let mut r = Reminders::empty(ephemeral_path(".reminders-rotate"), date(2025, 6, 10), &options);
+        r.load("2023 12 31 party\n2024 3 1 dentist\n2024 1 5 haircut\n2025 6 12 lunch\n", &options, false).unwrap();
+        assert_eq!(r.rotate().unwrap(), "archived 3 reminders in 2 files\n");
+        assert!(r.purged.is_empty());
+        assert_eq!(r.history, vec!("archive 2023 12 31 party", "archive 2024 3 1 dentist", "archive 2024 1 5 haircut"));
+        assert_eq!(r.read_archive("2023").unwrap(), "2023 12 31 party\n");
+        assert_eq!(r.read_archive("2024").unwrap(), "2024 3 1 dentist\n2024 1 5 haircut\n");
+        assert_eq!(r.read_archive("2022"), Err("no archive for 2022".to_string()));
+        assert_eq!(r.persistable_lines(), "2025 6 12 lunch\n");
+        for year in ["2023", "2024"] {
+            std::fs::remove_file(r.sibling_path(&format!(".{}", year))).unwrap();
+        }
+    }
+
+    #[test]
+    fn nearest_moves_a_day_past_the_month_to_its_last_day() {
+        let r = reminders(date(2025, 6, 10), "");
+        assert_eq!(r.parse_item(args("7 32 pay rent"), true).unwrap().to_string(), "7 31 pay rent");
+        assert_eq!(r.parse_item(args("2023 2 29 pay rent"), true).unwrap().to_string(), "2023 2 28 pay rent");
+        // without a year February has 29 days
+        assert_eq!(r.parse_item(args("2 30 pay rent"), true).unwrap().date, date(2028, 2, 29));
+        assert!(r.parse_item(args("7 32 pay rent"), false).is_err());
+        assert!(r.parse_item(args("2023 2 29 pay rent"), false).is_err());
+        assert!(r.parse_item(args("13 1 pay rent"), true).is_err());
+        assert!(r.parse_item(args("7 0 pay rent"), true).is_err());
+    }
+
+    #[test]
+    fn history_logs_each_addition_and_deletion() {
+        let options = Options { history: true, ..Options::default() };
+        let mut r = Reminders::empty(ephemeral_path(".reminders-history"), date(2025, 6, 10), &options);
+        r.load("2025 6 1 old\n2025 6 10 dentist\n", &options, false).unwrap();
+        r.add_new(ReminderItem::new(date(2025, 6, 13), false, None, "lunch with Pat".to_string()));
+        r.add_new(ReminderItem::new(date(2025, 6, 9), false, None, "yesterday".to_string()));
+        r.write_history();
+        // as on the next run
+        r.history.clear();
+        r.review("d\n".as_bytes(), std::io::sink()).unwrap();
+        r.write_history();
+        let entries = r.read_history().unwrap()
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec!("purge 2025 6 1 old", "add 2025 6 13 lunch with Pat", "add 2025 6 9 yesterday",
+            "purge 2025 6 9 yesterday", "delete 2025 6 10 dentist"));
+        std::fs::remove_file(r.sibling_path(".log")).unwrap();
+        let r = Reminders::empty(ephemeral_path(".reminders-history"), date(2025, 6, 10), &Options::default());
+        assert_eq!(r.read_history(), Ok(String::new()));
+    }
+
+    #[test]
+    fn message_after_a_double_dash_is_kept_as_given() {
+        let r = reminders(date(2025, 6, 10), "");
+        let item = r.parse_item(args("7 4 -- 2025 budget review"), false).unwrap();
+        assert_eq!((item.message.as_str(), item.recurring), ("2025 budget review", true));
+        assert_eq!(item.to_string(), "7 4 2025 budget review");
+        let item = r.parse_item(args("2025 7 4 -- -- !1 not a priority"), false).unwrap();
+        assert_eq!((item.message.as_str(), item.priority), ("-- !1 not a priority", None));
+        let lines = "7 4 2025 budget review\n2025 7 4 -- -- !1 not a priority\n";
+        let r = reminders(date(2025, 6, 10), lines);
+        assert_eq!(r.reminder_items[1].message, "2025 budget review");
+        assert_eq!(r.persistable_lines(), "2025 7 4 -- -- !1 not a priority\n7 4 2025 budget review\n");
+    }
+
+    #[test]
+    fn status_gives_the_number_due_today_and_the_next_date() {
+        let r = reminders(date(2025, 6, 10), "2025 6 10 dentist\n6 10 Anne birthday\n2025 6 12 lunch with Pat\n");
+        let status = r.status();
+        assert!(status.starts_with("{\"generated\": \""), "{}", status);
+        assert!(status.ends_with("\", \"due\": 2, \"next\": \"2025-06-10\"}\n"), "{}", status);
+        let status = reminders(date(2025, 6, 10), "2025 6 12 lunch with Pat\n").status();
+        assert!(status.ends_with("\"due\": 0, \"next\": \"2025-06-12\"}\n"), "{}", status);
+        assert!(reminders(date(2025, 6, 10), "").status().ends_with("\"due\": 0, \"next\": null}\n"));
+        let path = ephemeral_path(".status");
+        write_atomically(&path, &status).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), status);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn notes_are_written_on_one_line_and_shown_only_in_details() {
+        let mut r = reminders(date(2025, 6, 10), "2025 6 12 dentist\n");
+        r.reminder_items[0].note = Some("suite \"410\", C:\\dir\nbring card\r".to_string());
+        let lines = "2025 6 12 dentist note=\"suite \\\"410\\\", C:\\\\dir\\nbring card\\r\"\n";
+        assert_eq!(r.persistable_lines(), lines);
+        let r = reminders(date(2025, 6, 10), lines);
+        assert_eq!(r.reminder_items[0].note.as_deref(), Some("suite \"410\", C:\\dir\nbring card\r"));
+        assert_eq!(r.persistable_lines(), lines);
+        assert_eq!(r.show("dentist").unwrap(), "2025 6 12 dentist\nsuite \"410\", C:\\dir\nbring card\r\n");
+        let options = Options { no_summary: true, ..Options::default() };
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 12 dentist\n");
+        let options = Options { details: true, no_summary: true, ..Options::default() };
+        assert_eq!(r.listing(&options).unwrap(), "2025 6 12 dentist\n          suite \"410\", C:\\dir\nbring card\r\n");
+    }
+
+    #[test]
+    fn line_numbers_count_the_format_header() {
+        let r = reminders(date(2025, 6, 10), "2025 6 12 dentist\n2025 6 13 lunch with Pat\n");
+        assert_eq!(r.select("2"), vec!(1));
+        let r = reminders(date(2025, 6, 10), "#remind-format 2\n2025 6 12 dentist\n2025 6 13 lunch with Pat\ncron \"0 9 1 * *\" rent\n");
+        assert_eq!(r.persistable_lines().lines().nth(2), Some("2025 6 13 lunch with Pat"));
+        assert_eq!(r.select("3"), vec!(1));
+        assert_eq!(r.select("4"), vec!(2));
+        assert!(r.select("1").is_empty());
+        assert!(r.select("5").is_empty());
+    }
+
+    #[test]
+    fn year_placeholders_are_the_years_of_each_occurrence() {
+        let r = reminders(date(2025, 6, 10), "4 15 file {year-1} taxes\n");
+        let options = |start| Options { window: Some((start, 7)), no_summary: true, ..Options::default() };
+        assert_eq!(r.listing(&options(date(2026, 4, 12))).unwrap(), "4 15 file 2025 taxes\n");
+        assert_eq!(r.listing(&options(date(2027, 4, 12))).unwrap(), "4 15 file 2026 taxes\n");
+        assert_eq!(expand_years("{year} and {year+1}", 2025), "2025 and 2026");
+        assert_eq!(expand_years("party {yearé} {year-x} {year", 2025), "party {yearé} {year-x} {year");
+    }
+}